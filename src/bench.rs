@@ -0,0 +1,167 @@
+use crate::cache::Cache;
+use aoc_2021::registry::{Day, Input};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &mut [Duration]) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        samples.sort_unstable();
+        let n = samples.len();
+        let median = if n % 2 == 1 {
+            samples[n / 2]
+        } else {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        };
+        let mean = samples.iter().map(Duration::as_secs_f64).sum::<f64>() / n as f64;
+        let variance = samples
+            .iter()
+            .map(|s| (s.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / n as f64;
+        Self {
+            min: samples[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+/// Times `iterations` runs of `f` after a warm-up run
+pub fn measure<T, F: FnMut() -> T>(iterations: usize, mut f: F) -> Stats {
+    black_box(f());
+    let mut samples: Vec<_> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect();
+    Stats::from_samples(&mut samples)
+}
+
+/// Median timings keyed by `day<N> generator` or `day<N> part<M>`
+pub type Baseline = BTreeMap<String, Duration>;
+
+/// Reads a baseline saved by `save_baseline`: one `<key>\t<median in nanoseconds>` per line
+pub fn load_baseline(path: &Path) -> Result<Baseline, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let invalid = || format!("{}:{}: invalid baseline entry", path.display(), i + 1);
+            let (key, nanos) = line.split_once('\t').ok_or_else(invalid)?;
+            let nanos = nanos.trim().parse().map_err(|_| invalid())?;
+            Ok((key.to_string(), Duration::from_nanos(nanos)))
+        })
+        .collect()
+}
+
+pub fn save_baseline(path: &Path, baseline: &Baseline) -> Result<(), String> {
+    let content: String = baseline
+        .iter()
+        .map(|(key, median)| format!("{}\t{}\n", key, median.as_nanos()))
+        .collect();
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn print_row(key: &str, stats: &Stats, previous: Option<Duration>) {
+    let cell = |d: Duration| format!("{:>12}", format!("{:.2?}", d));
+    let mut row = format!(
+        "{:<16} min {} median {} mean {} stddev {}",
+        key,
+        cell(stats.min),
+        cell(stats.median),
+        cell(stats.mean),
+        cell(stats.stddev)
+    );
+    if let Some(previous) = previous {
+        let change = stats.median.as_secs_f64() / previous.as_secs_f64() - 1.0;
+        row += &format!("  baseline {} ({:+.1}%)", cell(previous), change * 100.0);
+    }
+    println!("{}", row);
+}
+
+/// Benchmarks the generator of `day` and then every selected part on its output, recording the
+/// medians into `results`. With a cache the input is not parsed again, and reading its snapshot
+/// back is timed instead of the generator.
+pub fn bench_day(
+    day: &Day,
+    parts: &[u8],
+    input: &str,
+    cache: Option<&Cache>,
+    iterations: usize,
+    baseline: Option<&Baseline>,
+    results: &mut Baseline,
+) -> Result<(), String> {
+    let parsed: Input = match cache {
+        Some(cache) => cache.generate(day, input)?,
+        None => day.generate(input).map_err(|e| e.to_string())?,
+    };
+    let mut record = |key: String, stats: Stats| {
+        print_row(&key, &stats, baseline.and_then(|b| b.get(&key).copied()));
+        results.insert(key, stats.median);
+    };
+
+    if cache.is_some() {
+        let snapshot = day.snapshot(&parsed);
+        let stats = measure(iterations, || day.restore(&snapshot));
+        record(format!("day{} restore", day.day), stats);
+    } else {
+        let stats = measure(iterations, || day.generate(input));
+        record(format!("day{} generator", day.day), stats);
+    }
+    for &part in parts {
+        let solver = day.solver(part, None).map_err(|e| e.to_string())?;
+        // an input the solver fails on has no time worth recording
+        solver.solve(&parsed).map_err(|e| e.to_string())?;
+        let stats = measure(iterations, || solver.solve(&parsed));
+        record(format!("day{} part{}", day.day, part), stats);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut samples: Vec<_> = [4, 1, 3, 2]
+            .iter()
+            .map(|&n| Duration::from_millis(n))
+            .collect();
+        let stats = Stats::from_samples(&mut samples);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_micros(2500));
+        assert_eq!(stats.mean.as_micros(), 2500);
+        assert_eq!(stats.stddev.as_micros(), 1118);
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let path = std::env::temp_dir().join(format!("aoc-2021-baseline-{}", std::process::id()));
+        let mut baseline = Baseline::new();
+        baseline.insert("day15 part2".to_string(), Duration::from_nanos(1234567));
+        baseline.insert("day15 generator".to_string(), Duration::from_nanos(89));
+        save_baseline(&path, &baseline).unwrap();
+        assert_eq!(load_baseline(&path), Ok(baseline));
+        fs::write(&path, "day1 part1 12").unwrap();
+        assert!(load_baseline(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+use aoc_2021::registry::{Day, Input};
+use std::fs;
+use std::path::PathBuf;
+
+/// Start of every cache file, followed by the snapshot format version and the day
+const MAGIC: &[u8] = b"aoc-2021";
+const VERSION: u8 = 2;
+
+/// FNV-1a, which unlike the standard hasher gives the same value on every run
+pub fn hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Directory of parsed inputs, one `day<N>-<hash of the input>.bin` snapshot per input
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file(&self, day: u8, input: &str) -> PathBuf {
+        self.dir
+            .join(format!("day{}-{:016x}.bin", day, hash(input)))
+    }
+
+    /// The stored parse of `input`, `None` when there is none or it can't be read back
+    pub fn load(&self, day: &Day, input: &str) -> Option<Input> {
+        let bytes = fs::read(self.file(day.day, input)).ok()?;
+        let snapshot = match bytes.strip_prefix(MAGIC)? {
+            [VERSION, number, snapshot @ ..] if *number == day.day => snapshot,
+            _ => return None,
+        };
+        day.restore(snapshot)
+    }
+
+    pub fn store(&self, day: &Day, input: &str, parsed: &Input) -> Result<(), String> {
+        let file = self.file(day.day, input);
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([VERSION, day.day]);
+        bytes.extend(day.snapshot(parsed));
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&file, bytes))
+            .map_err(|e| format!("{}: {}", file.display(), e))
+    }
+
+    /// Restores `input` from the cache, or parses it and stores the result for the next run. A
+    /// cache that can't be written only costs a warning.
+    pub fn generate(&self, day: &Day, input: &str) -> Result<Input, String> {
+        if let Some(parsed) = self.load(day, input) {
+            return Ok(parsed);
+        }
+        let parsed = day.generate(input).map_err(|e| e.to_string())?;
+        if let Err(err) = self.store(day, input, &parsed) {
+            eprintln!("warning: could not cache day {}: {}", day.day, err);
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_2021::registry::{self, Answer};
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(""), 0xcbf29ce484222325);
+        assert_eq!(hash("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("aoc-2021-cache-{}", std::process::id()));
+        let cache = Cache::new(dir.clone());
+        let day = registry::day(6).unwrap();
+        let solve = |parsed: &Input| day.solver(1, None).unwrap().solve(parsed);
+        assert!(cache.load(day, "3,4,3,1,2").is_none());
+
+        let parsed = cache.generate(day, "3,4,3,1,2").unwrap();
+        let cached = cache.load(day, "3,4,3,1,2").unwrap();
+        assert_eq!(solve(&cached), solve(&parsed));
+        assert_eq!(solve(&cached), Ok(Answer::U64(5934)));
+        assert!(cache.load(registry::day(7).unwrap(), "3,4,3,1,2").is_none());
+
+        // a damaged file is parsed again and replaced
+        let file = cache.file(6, "3,4,3,1,2");
+        fs::write(&file, b"aoc-2021\x02\x06\x05").unwrap();
+        assert!(cache.load(day, "3,4,3,1,2").is_none());
+        cache.generate(day, "3,4,3,1,2").unwrap();
+        assert!(cache.load(day, "3,4,3,1,2").is_some());
+        assert!(cache.generate(day, "3,x").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,361 @@
+use crate::pool;
+use crate::report::Format;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "Usage: aoc-2021 [COMMAND] [OPTIONS]
+
+Commands:
+  run                   print the answers with generator and solver timings [default]
+  bench                 time every generator and solver over several iterations
+  verify                check the answers against the ledger of known answers
+  synth                 write random puzzle inputs instead of solving
+
+Options:
+  -d, --day <DAYS>      days to run, e.g. `5`, `1-5`, `1,3,7` or `all` [default: all]
+                        Several days need `--input <DIR>`, or `--output <DIR>` for `synth`
+  -p, --part <PARTS>    parts to run, e.g. `1`, `2` or `all` [default: all]
+  -i, --input <PATH>    input file, or a directory holding `day<N>.txt` files when running
+                        several days. Reads the day given with `--day <N>` from stdin when
+                        omitted or `-`
+  -f, --format <FORMAT> output of `run`: `text`, `json` (one object per line) or `csv`
+                        [default: text]
+  -c, --cross-check     run every variant of a part and fail if their answers differ
+  -j, --jobs <N>        days and parts that `run` and `verify` solve at the same time
+                        [default: the number of CPUs]
+  -t, --timeout <SECS>  give up on a part that runs longer than this
+      --budget <STEPS>  give up on a part that takes more steps in its open-ended loops
+  -v, --visualize       animate days 11, 13, 15 and 20 in the terminal before their answers
+      --frame-delay <MS>
+                        pause between two frames of an animation [default: 100]
+  -e, --export <DIR>    save pictures of days 2, 9, 13, 15 and 20 as PBM, PGM or PPM images
+      --cache <DIR>     keep the parsed inputs there and read them back instead of parsing the
+                        same input again
+  -h, --help            print this message
+
+Bench options:
+  -n, --iterations <N>      timed runs per generator and solver [default: 20]
+      --baseline <FILE>     compare the medians against a saved baseline
+      --save-baseline <FILE>
+                            save the medians so later runs can be compared against them
+
+Verify options:
+  -a, --answers <FILE>  ledger of known answers [default: answers.toml]
+      --record          add the answers missing from the ledger to it
+
+Synth options:
+      --size <N>        lines, map side or items of each input [default: the real puzzle size]
+      --seed <N>        seed of the random inputs [default: 0]
+  -o, --output <DIR>    write `day<N>.txt` files there instead of printing a single day";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Bench,
+    Verify,
+    Synth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    pub days: Vec<u8>,
+    /// `None` runs every part a day has
+    pub parts: Option<Vec<u8>>,
+    pub input: Option<PathBuf>,
+    pub format: Format,
+    pub cross_check: bool,
+    pub jobs: usize,
+    /// `None` lets every part run for as long as it takes
+    pub timeout: Option<Duration>,
+    pub budget: Option<u64>,
+    pub visualize: bool,
+    pub frame_delay: Duration,
+    pub export: Option<PathBuf>,
+    pub cache: Option<PathBuf>,
+    pub iterations: usize,
+    pub baseline: Option<PathBuf>,
+    pub save_baseline: Option<PathBuf>,
+    pub answers: PathBuf,
+    pub record: bool,
+    /// `None` uses the size of the real puzzle input of each day
+    pub size: Option<usize>,
+    pub seed: u64,
+    pub output: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            command: Command::Run,
+            days: (1..=25).collect(),
+            parts: None,
+            input: None,
+            format: Format::Text,
+            cross_check: false,
+            jobs: pool::default_jobs(),
+            timeout: None,
+            budget: None,
+            visualize: false,
+            frame_delay: Duration::from_millis(100),
+            export: None,
+            cache: None,
+            iterations: 20,
+            baseline: None,
+            save_baseline: None,
+            answers: PathBuf::from("answers.toml"),
+            record: false,
+            size: None,
+            seed: 0,
+            output: None,
+            help: false,
+        }
+    }
+}
+
+/// Parses a selection such as `3`, `1-5`, `1,4-6` or `all` into a sorted list
+fn parse_selection(spec: &str, max: u8) -> Result<Vec<u8>, String> {
+    if spec == "all" {
+        return Ok((1..=max).collect());
+    }
+    let number = |s: &str| -> Result<u8, String> {
+        match s.trim().parse() {
+            Ok(n) if (1..=max).contains(&n) => Ok(n),
+            _ => Err(format!("`{}` is not a number between 1 and {}", s, max)),
+        }
+    };
+    let mut selection = Vec::new();
+    for item in spec.split(',') {
+        match item.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (number(start)?, number(end)?);
+                if start > end {
+                    return Err(format!("empty range `{}`", item));
+                }
+                selection.extend(start..=end);
+            }
+            None => selection.push(number(item)?),
+        }
+    }
+    selection.sort_unstable();
+    selection.dedup();
+    Ok(selection)
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek().map(String::as_str) {
+        Some("run") => Some(Command::Run),
+        Some("bench") => Some(Command::Bench),
+        Some("verify") => Some(Command::Verify),
+        Some("synth") => Some(Command::Synth),
+        _ => None,
+    };
+    if let Some(command) = command {
+        options.command = command;
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.into())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for `{}`", flag))
+        };
+        match flag.as_str() {
+            "-d" | "--day" => options.days = parse_selection(&value()?, 25)?,
+            "-p" | "--part" => options.parts = Some(parse_selection(&value()?, 2)?),
+            "-i" | "--input" => options.input = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let val = value()?;
+                options.format =
+                    Format::parse(&val).ok_or_else(|| format!("unknown format `{}`", val))?;
+            }
+            "-c" | "--cross-check" => options.cross_check = true,
+            "-j" | "--jobs" => {
+                let val = value()?;
+                options.jobs = match val.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("`{}` is not a positive number", val)),
+                }
+            }
+            "-t" | "--timeout" => {
+                let val = value()?;
+                options.timeout = match val.parse::<f64>() {
+                    Ok(secs) if secs > 0.0 && secs.is_finite() => {
+                        Some(Duration::from_secs_f64(secs))
+                    }
+                    _ => return Err(format!("`{}` is not a positive number of seconds", val)),
+                }
+            }
+            "--budget" => {
+                let val = value()?;
+                options.budget = match val.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("`{}` is not a positive number", val)),
+                }
+            }
+            "-v" | "--visualize" => options.visualize = true,
+            "--frame-delay" => {
+                let val = value()?;
+                let millis = val
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a number of milliseconds", val))?;
+                options.frame_delay = Duration::from_millis(millis);
+            }
+            "-n" | "--iterations" => {
+                let val = value()?;
+                options.iterations = match val.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("`{}` is not a positive number", val)),
+                }
+            }
+            "--baseline" => options.baseline = Some(PathBuf::from(value()?)),
+            "--save-baseline" => options.save_baseline = Some(PathBuf::from(value()?)),
+            "-e" | "--export" => options.export = Some(PathBuf::from(value()?)),
+            "--cache" => options.cache = Some(PathBuf::from(value()?)),
+            "-a" | "--answers" => options.answers = PathBuf::from(value()?),
+            "--record" => options.record = true,
+            "--size" => {
+                let val = value()?;
+                options.size = match val.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("`{}` is not a positive number", val)),
+                }
+            }
+            "--seed" => {
+                let val = value()?;
+                options.seed = val
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a valid seed", val))?;
+            }
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            "-h" | "--help" => options.help = true,
+            _ => return Err(format!("unknown argument `{}`", flag)),
+        }
+    }
+    Ok(options)
+}
+
+/// Several days read their inputs from a directory, or write them to one for `synth`. Only a
+/// single day, which has to be picked with `--day` since all of them run by default, can use
+/// stdin or stdout.
+pub fn check_days(options: &Options) -> Result<(), String> {
+    if options.days.len() == 1 {
+        return Ok(());
+    }
+    let (dir, flag, single) = match options.command {
+        Command::Synth => (options.output.as_deref(), "--output", "print its input"),
+        _ => (
+            options.input.as_deref(),
+            "--input",
+            "read its input from stdin",
+        ),
+    };
+    match dir {
+        Some(dir) if dir.is_dir() => Ok(()),
+        _ => Err(format!(
+            "{} days are selected, which needs `{} <DIR>`; pick a single day with `--day <N>` to {}",
+            options.days.len(),
+            flag,
+            single
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_selection() {
+        assert_eq!(parse_selection("all", 2), Ok(vec![1, 2]));
+        assert_eq!(parse_selection("7,1-3,2", 25), Ok(vec![1, 2, 3, 7]));
+        assert!(parse_selection("0", 25).is_err());
+        assert!(parse_selection("5-3", 25).is_err());
+        assert!(parse_selection("3", 2).is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(args(""), Ok(Options::default()));
+        let options = args("--day 3 -p 2 --input=in.txt").unwrap();
+        assert_eq!(options.days, vec![3]);
+        assert_eq!(options.parts, Some(vec![2]));
+        assert_eq!(options.input, Some(PathBuf::from("in.txt")));
+        assert_eq!(args("-f csv").unwrap().format, Format::Csv);
+        assert!(args("--format xml").is_err());
+        assert!(args("-c").unwrap().cross_check);
+        assert_eq!(args("verify -j 3").unwrap().jobs, 3);
+        assert!(args("--jobs 0").is_err());
+        let options = args("-t 1.5 --budget 1000").unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(options.budget, Some(1000));
+        assert!(args("--timeout -1").is_err());
+        assert!(args("--budget 0").is_err());
+        let options = args("-d 11 -v --frame-delay 20").unwrap();
+        assert!(options.visualize);
+        assert_eq!(options.frame_delay, Duration::from_millis(20));
+        assert!(args("--frame-delay fast").is_err());
+        assert_eq!(args("-e out").unwrap().export, Some(PathBuf::from("out")));
+        assert_eq!(args("--day"), Err("missing value for `--day`".to_string()));
+        assert!(args("--fast").is_err());
+    }
+
+    #[test]
+    fn test_check_days() {
+        let err = check_days(&args("").unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            "25 days are selected, which needs `--input <DIR>`; pick a single day with `--day <N>` \
+             to read its input from stdin"
+        );
+        assert!(check_days(&args("-d 3").unwrap()).is_ok());
+        assert!(check_days(&args("-d 1-2 -i day1.txt").unwrap()).is_err());
+        let dir = std::env::temp_dir().to_string_lossy().into_owned();
+        assert!(check_days(&args(&format!("-d 1-2 -i {}", dir)).unwrap()).is_ok());
+        let err = check_days(&args(&format!("synth -i {}", dir)).unwrap()).unwrap_err();
+        assert!(err.contains("`--output <DIR>`"));
+    }
+
+    #[test]
+    fn test_bench_args() {
+        let options = args("bench -d 15 -n 5 --baseline base.txt").unwrap();
+        assert_eq!(options.command, Command::Bench);
+        assert_eq!(options.iterations, 5);
+        assert_eq!(options.baseline, Some(PathBuf::from("base.txt")));
+        let options = args("bench --cache=parsed").unwrap();
+        assert_eq!(options.cache, Some(PathBuf::from("parsed")));
+        assert_eq!(args("run").unwrap().command, Command::Run);
+        assert!(args("bench -n 0").is_err());
+    }
+
+    #[test]
+    fn test_verify_args() {
+        let options = args("verify -i inputs --record").unwrap();
+        assert_eq!(options.command, Command::Verify);
+        assert_eq!(options.answers, PathBuf::from("answers.toml"));
+        assert!(options.record);
+    }
+
+    #[test]
+    fn test_synth_args() {
+        let options = args("synth -d 12 --size 6 --seed=42 -o inputs").unwrap();
+        assert_eq!(options.command, Command::Synth);
+        assert_eq!(options.size, Some(6));
+        assert_eq!(options.seed, 42);
+        assert_eq!(options.output, Some(PathBuf::from("inputs")));
+        assert!(args("synth --seed -1").is_err());
+        assert!(args("synth --size 0").is_err());
+    }
+}
@@ -1,54 +1,170 @@
+use crate::parse::{lines, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Depths of `input` one line at a time, for logs too big to collect first
+pub fn depths(input: &str) -> impl Iterator<Item = Result<i64, ParseError>> + '_ {
+    lines(1, input).map(|l| l.parse(l.text))
+}
 
 #[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, ParseError> {
+    depths(input).collect()
 }
 
-#[aoc(day1, part1, d11)]
-pub fn part1(nums: &[i64]) -> i64 {
+/// Number of times the sum of `k` consecutive depths grows from one window to the next. Two
+/// neighbouring windows share all but one depth each, so only the depth entering the window and
+/// the one leaving it are compared, and only the last `k` depths are kept around.
+pub fn window_increases<I: IntoIterator<Item = i64>>(depths: I, k: usize) -> usize {
+    let mut window = VecDeque::with_capacity(k + 1);
     let mut count = 0;
-    let mut prev = None;
-    for &val in nums {
-        if let Some(p) = prev {
-            if p < val {
-                count += 1;
-            }
+    for depth in depths {
+        window.push_back(depth);
+        if window.len() > k && window.pop_front().is_some_and(|leaving| leaving < depth) {
+            count += 1;
         }
-        prev = Some(val);
     }
     count
 }
 
+#[aoc(day1, part1, d11)]
+pub fn part1(nums: &[i64]) -> i64 {
+    window_increases(nums.iter().copied(), 1) as i64
+}
+
 #[aoc(day1, part2, d12)]
 pub fn part2(nums: &[i64]) -> i64 {
-    let mut count = 0;
-    let mut prev_sum = nums[0..3].iter().fold(0, |acc, el| acc + el);
-    for i in 3..nums.len() {
-        let cur_sum = prev_sum - nums[i - 3] + nums[i];
-        if prev_sum < cur_sum {
-            count += 1;
-        }
-        prev_sum = cur_sum;
+    window_increases(nums.iter().copied(), 3) as i64
+}
+
+/// Readings in the longest stretch where every depth is deeper than the one before
+pub fn longest_increasing_run(nums: &[i64]) -> usize {
+    nums.chunk_by(|a, b| a < b)
+        .map(<[i64]>::len)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Largest decrease from one depth to the next, 0 when the depths never decrease
+pub fn largest_drop(nums: &[i64]) -> i64 {
+    nums.windows(2)
+        .map(|w| w[0] - w[1])
+        .max()
+        .unwrap_or(0)
+        .max(0)
+}
+
+/// Average depth of every `k` consecutive readings
+pub fn moving_averages(nums: &[i64], k: usize) -> Vec<f64> {
+    if k == 0 {
+        return Vec::new();
     }
-    count
+    nums.windows(k)
+        .map(|w| w.iter().sum::<i64>() as f64 / k as f64)
+        .collect()
+}
+
+/// Number of times each change of depth between two readings occurs
+pub fn delta_histogram(nums: &[i64]) -> BTreeMap<i64, usize> {
+    nums.windows(2)
+        .map(|w| w[1] - w[0])
+        .counts()
+        .into_iter()
+        .collect()
+}
+
+// The registry only deals in answers, so the analytics that don't come down to a single number
+// are listed as text
+
+/// Averages over the same windows as part 2, rounded to two decimals
+pub fn moving_average_output(nums: &[i64]) -> String {
+    moving_averages(nums, 3)
+        .iter()
+        .map(|avg| format!("{:.2}", avg))
+        .join(",")
+}
+
+/// `delta:count` pairs by increasing delta
+pub fn delta_histogram_output(nums: &[i64]) -> String {
+    delta_histogram(nums)
+        .iter()
+        .map(|(delta, count)| format!("{}:{}", delta, count))
+        .join(",")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth::Rng;
     const SAMPLE1: &str = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
     const SAMPLE2: &str = "607\n618\n618\n617\n647\n716\n769\n792";
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE1);
+        let input = input_generator(SAMPLE1).unwrap();
         assert_eq!(part1(&input), 7);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE2);
+        let input = input_generator(SAMPLE2).unwrap();
         assert_eq!(part2(&input), 5);
     }
+
+    #[test]
+    fn test_analytics() {
+        let input = input_generator(SAMPLE1).unwrap();
+        assert_eq!(longest_increasing_run(&input), 4);
+        assert_eq!(largest_drop(&input), 10);
+        assert_eq!(moving_averages(&input[..5], 3), [607.0 / 3.0, 206.0, 206.0]);
+        let histogram = delta_histogram(&input);
+        assert_eq!(histogram.values().sum::<usize>(), input.len() - 1);
+        assert_eq!((histogram[&-10], histogram[&33]), (1, 1));
+        assert_eq!(moving_average_output(&input[..4]), "202.33,206.00");
+        assert_eq!(delta_histogram_output(&[1, 3, 5, 4]), "-1:1,2:2");
+
+        assert_eq!(longest_increasing_run(&[]), 0);
+        assert_eq!(longest_increasing_run(&[5, 5, 5]), 1);
+        assert_eq!(largest_drop(&[1, 2, 3]), 0);
+        assert!(moving_averages(&[1, 2], 3).is_empty());
+        assert!(delta_histogram(&[7]).is_empty());
+    }
+
+    #[test]
+    fn test_short_input() {
+        let input = input_generator("").unwrap();
+        assert_eq!((part1(&input), part2(&input)), (0, 0));
+        let input = input_generator("3\n1\n4").unwrap();
+        assert_eq!((part1(&input), part2(&input)), (1, 0));
+        assert_eq!(window_increases([3, 1, 4, 5], 3), 1);
+        assert_eq!(window_increases([1, 2], 0), 0);
+    }
+
+    #[test]
+    fn test_windows() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let depths: Vec<i64> = (0..rng.below(20)).map(|_| rng.range(0..=50)).collect();
+            let k = rng.below(6) + 1;
+            let sums: Vec<i64> = depths.windows(k).map(|w| w.iter().sum()).collect();
+            let expected = sums.windows(2).filter(|w| w[0] < w[1]).count();
+            assert_eq!(
+                window_increases(depths.clone(), k),
+                expected,
+                "{:?}",
+                depths
+            );
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        // a sawtooth of a million readings, where the window grows unless its new reading
+        // wrapped around to 0, 1 or 2, and nothing gets collected
+        let sawtooth = (0..1_000_000).map(|i| i % 10);
+        assert_eq!(window_increases(sawtooth, 3), 700_000);
+        let streamed = depths("1\n2\n3\n4\n5").map(Result::unwrap);
+        assert_eq!(window_increases(streamed, 2), 3);
+    }
 }
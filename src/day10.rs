@@ -1,11 +1,16 @@
+use crate::parse::{lines, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[aoc_generator(day10)]
-pub fn input_generator(input: &str) -> Vec<Vec<char>> {
-    input.lines().fold(Vec::new(), |mut acc, line| {
-        acc.push(line.chars().collect());
-        acc
-    })
+pub fn input_generator(input: &str) -> Result<Vec<Vec<char>>, ParseError> {
+    lines(10, input)
+        .map(|line| {
+            if let Some(i) = line.text.find(|c| !"()[]{}<>".contains(c)) {
+                return Err(line.error_at(&line.text[i..], "expected a bracket"));
+            }
+            Ok(line.text.chars().collect())
+        })
+        .collect()
 }
 
 #[aoc(day10, part1, d101)]
@@ -86,13 +91,13 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 26_397);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 288_957);
     }
 }
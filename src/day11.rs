@@ -1,8 +1,12 @@
+use crate::grid::Grid;
+use crate::parse::{lines, ParseError};
+use crate::simulation::{Periodic, Simulation};
+use crate::snapshot::Snapshot;
+use crate::viz::Frames;
 use aoc_runner_derive::{aoc, aoc_generator};
 use colored::*;
-use prettytable::{Cell, Row, Table};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Octopus {
     Unflashed(u8),
     Flashed,
@@ -18,178 +22,155 @@ impl std::fmt::Display for Octopus {
     }
 }
 
-#[allow(dead_code)]
-fn print_table(grid: &[Vec<Octopus>], step: usize, count: u64) {
-    let mut table = Table::new();
-
-    table.set_titles(Row::new(vec![
-        Cell::new(&step.to_string()),
-        Cell::new(&count.to_string()),
-    ]));
-    for row in grid {
-        let formatted: Vec<_> = row.iter().map(|n| Cell::new(&n.to_string())).collect();
-        table.add_row(Row::new(formatted));
+impl Snapshot for Octopus {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Octopus::Unflashed(energy) => energy.encode(out),
+            Octopus::Flashed => out.push(u8::MAX),
+        }
     }
 
-    table.printstd();
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(match u8::decode(input)? {
+            u8::MAX => Octopus::Flashed,
+            energy => Octopus::Unflashed(energy),
+        })
+    }
 }
 
 #[aoc_generator(day11)]
-pub fn input_generator(input: &str) -> Vec<Vec<Octopus>> {
-    input.lines().fold(Vec::new(), |mut acc, line| {
-        acc.push(
-            line.chars()
-                .map(|c| Octopus::Unflashed(c.to_digit(10).unwrap() as u8))
-                .collect(),
-        );
-        acc
-    })
+pub fn input_generator(input: &str) -> Result<Grid<Octopus>, ParseError> {
+    Ok(Grid::from_digits(lines(11, input))?.map(|&energy| Octopus::Unflashed(energy)))
 }
 
-const ADJENCENT: [(i32, i32); 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
-#[aoc(day11, part1, d111)]
-pub fn part1(grid: &[Vec<Octopus>]) -> u64 {
-    let mut grid = grid.to_owned();
-    let (m, n) = (grid.len(), grid[0].len());
-    let mut total_flashes = 0;
-    for _ in 0..100 {
-        let mut flash_points = Vec::new();
-        for row in 0..m {
-            for col in 0..n {
-                match grid[row].get_mut(col).unwrap() {
-                    Octopus::Unflashed(val) => {
-                        *val += 1;
-                        if *val > 9 {
-                            flash_points.push((row, col));
-                        }
-                    }
-                    _ => panic!("SHIT"),
-                }
-            }
-        }
+#[derive(Debug, Clone)]
+struct Cavern {
+    grid: Grid<Octopus>,
+    /// Octopuses that flashed during the last step
+    flashed: usize,
+    total: u64,
+}
 
-        while let Some((row, col)) = flash_points.pop() {
-            if grid[row][col] == Octopus::Flashed {
-                continue;
-            }
-            grid[row][col] = Octopus::Flashed;
-            total_flashes += 1;
-
-            let neighbors: Vec<_> = ADJENCENT
-                .into_iter()
-                .flat_map(|(or, oc)| {
-                    let (r, c) = (row as i32 + or, col as i32 + oc);
-                    if r >= 0
-                        && r < m as i32
-                        && c >= 0
-                        && c < n as i32
-                        && grid[r as usize][c as usize] != Octopus::Flashed
-                    {
-                        Some((r as usize, c as usize))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            for (r, c) in neighbors {
-                if let Octopus::Unflashed(val) = grid[r].get_mut(c).unwrap() {
-                    *val += 1;
-                    if *val > 9 {
-                        flash_points.push((r, c));
-                    }
-                } else {
-                    panic!("SHIT");
-                }
-            }
-        }
-        for row in 0..m {
-            for col in 0..n {
-                if grid[row][col] == Octopus::Flashed {
-                    grid[row][col] = Octopus::Unflashed(0);
-                }
-            }
+impl Cavern {
+    fn new(grid: &Grid<Octopus>) -> Self {
+        Self {
+            grid: grid.clone(),
+            flashed: 0,
+            total: 0,
         }
     }
-    total_flashes
 }
 
-#[aoc(day11, part2, d112)]
-pub fn part2(grid: &[Vec<Octopus>]) -> u64 {
-    let mut grid = grid.to_owned();
-    let (m, n) = (grid.len(), grid[0].len());
-    for step in 0.. {
+impl Simulation for Cavern {
+    fn step(&mut self) {
+        let grid = &mut self.grid;
         let mut flash_count = 0;
         let mut flash_points = Vec::new();
-        for row in 0..m {
-            for col in 0..n {
-                match grid[row].get_mut(col).unwrap() {
-                    Octopus::Unflashed(val) => {
-                        *val += 1;
-                        if *val > 9 {
-                            flash_points.push((row, col));
-                        }
+        for pos in grid.positions() {
+            match &mut grid[pos] {
+                Octopus::Unflashed(val) => {
+                    *val += 1;
+                    if *val > 9 {
+                        flash_points.push(pos);
                     }
-                    _ => panic!("SHIT"),
                 }
+                _ => panic!("SHIT"),
             }
         }
 
-        while let Some((row, col)) = flash_points.pop() {
-            if grid[row][col] == Octopus::Flashed {
+        while let Some(pos) = flash_points.pop() {
+            if grid[pos] == Octopus::Flashed {
                 continue;
             }
-            grid[row][col] = Octopus::Flashed;
+            grid[pos] = Octopus::Flashed;
             flash_count += 1;
 
-            let neighbors: Vec<_> = ADJENCENT
-                .into_iter()
-                .flat_map(|(or, oc)| {
-                    let (r, c) = (row as i32 + or, col as i32 + oc);
-                    if r >= 0
-                        && r < m as i32
-                        && c >= 0
-                        && c < n as i32
-                        && grid[r as usize][c as usize] != Octopus::Flashed
-                    {
-                        Some((r as usize, c as usize))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            for (r, c) in neighbors {
-                if let Octopus::Unflashed(val) = grid[r].get_mut(c).unwrap() {
+            for neighbour in grid.neighbours8(pos) {
+                if let Octopus::Unflashed(val) = &mut grid[neighbour] {
                     *val += 1;
                     if *val > 9 {
-                        flash_points.push((r, c));
+                        flash_points.push(neighbour);
                     }
-                } else {
-                    panic!("SHIT");
                 }
             }
         }
-        if flash_count == m * n {
-            return step + 1;
-        }
-        for row in 0..m {
-            for col in 0..n {
-                if grid[row][col] == Octopus::Flashed {
-                    grid[row][col] = Octopus::Unflashed(0);
-                }
-            }
+        grid.iter_mut()
+            .filter(|octopus| **octopus == Octopus::Flashed)
+            .for_each(|octopus| *octopus = Octopus::Unflashed(0));
+        self.flashed = flash_count;
+        self.total += flash_count as u64;
+    }
+}
+
+/// Once the octopuses flash together they do it again every ten steps, while the flashes keep
+/// adding up
+impl Periodic for Cavern {
+    type Key = Grid<Octopus>;
+
+    fn key(&self) -> Grid<Octopus> {
+        self.grid.clone()
+    }
+
+    fn total(&self) -> u64 {
+        self.total
+    }
+
+    fn add_total(&mut self, amount: u64) {
+        self.total += amount;
+    }
+}
+
+pub fn flashes_after(grid: &Grid<Octopus>, steps: usize) -> u64 {
+    let mut cavern = Cavern::new(grid);
+    cavern.fast_forward(steps);
+    cavern.total
+}
+
+/// First step during which every octopus flashes, giving up after `limit` steps
+pub(crate) fn first_sync(grid: &Grid<Octopus>, limit: usize) -> Option<u64> {
+    let mut cavern = Cavern::new(grid);
+    for step in 1..=limit {
+        cavern.step();
+        if cavern.flashed == cavern.grid.len() {
+            return Some(step as u64);
         }
     }
-    unreachable!()
+    None
+}
+
+/// Steps until every octopus flashes at once, with the octopuses that just flashed lit up
+pub(crate) fn frames(grid: Grid<Octopus>) -> Frames {
+    const LIMIT: usize = 1000;
+    let mut cavern = Cavern::new(&grid);
+    let mut step = 0;
+    let mut done = false;
+    Box::new(std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        if step > 0 {
+            cavern.step();
+        }
+        done = cavern.flashed == cavern.grid.len() || step == LIMIT;
+        let octopuses = cavern.grid.map(|octopus| match octopus {
+            Octopus::Unflashed(0) => "0".bright_yellow().bold(),
+            octopus => octopus.to_string().dimmed(),
+        });
+        let frame = format!("step {}, {} flashes\n{}", step, cavern.total, octopuses);
+        step += 1;
+        Some(frame)
+    }))
+}
+
+#[aoc(day11, part1, d111)]
+pub fn part1(grid: &Grid<Octopus>) -> u64 {
+    flashes_after(grid, 100)
+}
+
+#[aoc(day11, part2, d112)]
+pub fn part2(grid: &Grid<Octopus>) -> u64 {
+    let mut cavern = Cavern::new(grid);
+    cavern.run_until(|c| c.flashed == c.grid.len()) as u64
 }
 
 #[cfg(test)]
@@ -208,13 +189,33 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(flashes_after(&input, 10), 204);
         assert_eq!(part1(&input), 1_656);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 195);
     }
+
+    #[test]
+    fn test_fast_forward() {
+        let input = input_generator(SAMPLE).unwrap();
+        for steps in [194, 195, 196, 500] {
+            let mut cavern = Cavern::new(&input);
+            cavern.run(steps);
+            assert_eq!(flashes_after(&input, steps), cavern.total);
+        }
+        // after the first sync all hundred octopuses flash together every ten steps
+        let mut cavern = Cavern::new(&input);
+        cavern.run(195);
+        let steps = 1_000_000_000;
+        let expected = cavern.total + 100 * ((steps - 195) / 10) as u64;
+        let mut cavern = Cavern::new(&input);
+        let cycle = cavern.fast_forward(steps).unwrap();
+        assert_eq!((cycle.start, cycle.length), (195, 10));
+        assert_eq!(cavern.total, expected);
+    }
 }
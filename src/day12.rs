@@ -1,5 +1,8 @@
+use crate::limits;
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_set, HashMap, HashSet};
 
 #[derive(Clone, Default)]
 pub struct Graph {
@@ -21,9 +24,9 @@ impl Graph {
         }
         self.edges
             .entry(from.clone())
-            .or_insert(HashSet::new())
+            .or_default()
             .insert(to.clone());
-        self.edges.entry(to).or_insert(HashSet::new()).insert(from);
+        self.edges.entry(to).or_default().insert(from);
     }
 
     fn get_edges(&self, from: &Cave) -> &HashSet<Cave> {
@@ -36,6 +39,19 @@ impl Graph {
     }
 }
 
+impl Snapshot for Graph {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.start.encode(out);
+        self.edges.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let start = Snapshot::decode(input)?;
+        let edges = Snapshot::decode(input)?;
+        Some(Self { start, edges })
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 enum Cave {
     Large(String),
@@ -43,12 +59,14 @@ enum Cave {
 }
 
 impl Cave {
-    fn to_tuple(name: &str) -> (Self, Self) {
-        let mut parts = name.split("-");
-        (
-            Self::from(parts.next().unwrap()),
-            Self::from(parts.next().unwrap()),
-        )
+    fn to_tuple(line: &Line) -> Result<(Self, Self), ParseError> {
+        let (from, to) = line.split_once(line.text, "-")?;
+        for name in [from, to] {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(line.error_at(name, format!("invalid cave name `{}`", name)));
+            }
+        }
+        Ok((Self::from(from), Self::from(to)))
     }
 
     fn from(name: &str) -> Self {
@@ -60,8 +78,8 @@ impl Cave {
 
     fn name(&self) -> &str {
         match self {
-            Cave::Large(name) => &name,
-            Cave::Small(name) => &name,
+            Cave::Large(name) => name,
+            Cave::Small(name) => name,
         }
     }
 
@@ -81,82 +99,99 @@ impl Cave {
     }
 }
 
-#[aoc_generator(day12)]
-pub fn input_generator(input: &str) -> Graph {
-    let mut graph = Graph::new();
-    for line in input.lines() {
-        let (from, to) = Cave::to_tuple(line);
-        graph.add_edge(from, to);
+/// Caves are stored by name, which tells large caves from small ones
+impl Snapshot for Cave {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.name().to_string().encode(out);
     }
-    graph
-}
 
-fn dfs_1(graph: &Graph, node: &Cave, visited: &mut HashSet<Cave>) -> u64 {
-    if visited.contains(node) {
-        return 0;
-    }
-    if node.is_end() {
-        return 1;
-    }
-    if node.is_small() {
-        visited.insert(node.clone());
-    }
-    let mut ways = 0;
-    for to in graph.get_edges(node) {
-        ways += dfs_1(graph, to, visited);
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(Self::from(&String::decode(input)?))
     }
-    visited.remove(node);
-    ways
 }
 
-fn dfs_2(graph: &Graph, node: &Cave, visited: &mut HashSet<Cave>, visit_used: &mut bool) -> u64 {
-    if visited.contains(node) && (node.is_start() || node.is_end() || *visit_used) {
-        return 0;
+#[aoc_generator(day12)]
+pub fn input_generator(input: &str) -> Result<Graph, ParseError> {
+    let mut graph = Graph::new();
+    for line in lines(12, input) {
+        let (from, to) = Cave::to_tuple(&line)?;
+        graph.add_edge(from, to);
     }
-    if node.is_end() {
-        return 1;
+    if graph.start.is_none() {
+        return Err(ParseError::eof(12, input, "no `start` cave"));
     }
+    Ok(graph)
+}
 
-    let mut double_visit = false;
-    if node.is_small() {
-        if visited.contains(node) && !*visit_used {
-            *visit_used = true;
-            double_visit = true
-        }
-        visited.insert(node.clone());
-    }
+/// How a cave on the current path was entered, so that leaving it can undo that
+enum Entry<'a> {
+    Large,
+    Small(&'a Cave),
+    /// The one small cave that was allowed a second visit
+    Twice,
+}
 
+/// Counts the paths from `start` to `end` that visit small caves once, except for a single small
+/// cave visited twice when `revisit` is set. The path is kept on a stack of its own rather than
+/// the call stack, since two adjacent large caves make paths as long as the limits allow.
+fn count_paths(graph: &Graph, revisit: bool) -> u64 {
+    let mut revisit_used = !revisit;
+    let mut visited = HashSet::new();
+    let mut path: Vec<(Entry, hash_set::Iter<Cave>)> = Vec::new();
     let mut ways = 0;
-    for to in graph.get_edges(node) {
-        ways += dfs_2(graph, to, visited, visit_used);
-    }
-
-    if !double_visit {
-        visited.remove(node);
-    }
-    if double_visit {
-        *visit_used = false
+    let mut next = Some(graph.start_cave());
+    loop {
+        if let Some(cave) = next.take() {
+            limits::step();
+            let seen = visited.contains(cave);
+            if seen && (cave.is_start() || cave.is_end() || revisit_used) {
+                // a dead end
+            } else if cave.is_end() {
+                ways += 1;
+            } else {
+                let entry = if !cave.is_small() {
+                    Entry::Large
+                } else if seen {
+                    revisit_used = true;
+                    Entry::Twice
+                } else {
+                    visited.insert(cave);
+                    Entry::Small(cave)
+                };
+                path.push((entry, graph.get_edges(cave).iter()));
+            }
+        }
+        let Some((_, edges)) = path.last_mut() else {
+            return ways;
+        };
+        next = edges.next();
+        if next.is_none() {
+            match path.pop().map(|(entry, _)| entry) {
+                Some(Entry::Small(cave)) => {
+                    visited.remove(cave);
+                }
+                Some(Entry::Twice) => revisit_used = false,
+                _ => {}
+            }
+        }
     }
-
-    ways
 }
 
 #[aoc(day12, part1, d121)]
 pub fn part1(graph: &Graph) -> u64 {
-    let mut visited = HashSet::new();
-    dfs_1(graph, graph.start_cave(), &mut visited)
+    count_paths(graph, false)
 }
 
 #[aoc(day12, part2, d122)]
 pub fn part2(graph: &Graph) -> u64 {
-    let mut visited = HashSet::new();
-    let mut visit_used = false;
-    dfs_2(graph, graph.start_cave(), &mut visited, &mut visit_used)
+    count_paths(graph, true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::limits::{Exceeded, Limits};
+    use std::time::Duration;
     const SAMPLE_1: &str = "start-A
 start-b
 A-c
@@ -197,21 +232,44 @@ start-RW";
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE_1);
+        let input = input_generator(SAMPLE_1).unwrap();
         assert_eq!(part1(&input), 10);
-        let input = input_generator(SAMPLE_2);
+        let input = input_generator(SAMPLE_2).unwrap();
         assert_eq!(part1(&input), 19);
-        let input = input_generator(SAMPLE_3);
+        let input = input_generator(SAMPLE_3).unwrap();
         assert_eq!(part1(&input), 226);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE_1);
+        let input = input_generator(SAMPLE_1).unwrap();
         assert_eq!(part2(&input), 36);
-        let input = input_generator(SAMPLE_2);
+        let input = input_generator(SAMPLE_2).unwrap();
         assert_eq!(part2(&input), 103);
-        let input = input_generator(SAMPLE_3);
+        let input = input_generator(SAMPLE_3).unwrap();
         assert_eq!(part2(&input), 3509);
     }
+
+    #[test]
+    fn test_endless_paths() {
+        // a walk between two large caves can go on forever
+        let input = input_generator("start-A\nA-B\nB-end").unwrap();
+        let budget = Limits {
+            budget: Some(100_000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            limits::run(budget, || part2(&input)),
+            Err(Exceeded::Budget(100_000))
+        );
+        let timeout = Duration::from_millis(50);
+        let limits = Limits {
+            timeout: Some(timeout),
+            ..Limits::default()
+        };
+        assert_eq!(
+            limits::run(limits, || part1(&input)),
+            Err(Exceeded::Timeout(timeout))
+        );
+    }
 }
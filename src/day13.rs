@@ -1,5 +1,9 @@
+use crate::export::Image;
+use crate::grid::Grid;
+use crate::parse::{lines, ParseError};
+use crate::snapshot::Snapshot;
+use crate::viz::{self, Frames};
 use aoc_runner_derive::{aoc, aoc_generator};
-use prettytable::{Cell as TableCell, Row, Table};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Cell {
@@ -22,161 +26,218 @@ pub enum Fold {
     X(usize),
 }
 
-#[aoc_generator(day13)]
-pub fn input_generator(input: &str) -> (Vec<Vec<Cell>>, Vec<Fold>) {
-    let (m, n) = find_size(input);
-    let mut matrix = vec![vec![Cell::Empty; n]; m];
-    let mut folds = Vec::new();
+impl Snapshot for Cell {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self == Cell::Full).encode(out);
+    }
 
-    let mut iter = input.lines();
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(match bool::decode(input)? {
+            true => Cell::Full,
+            false => Cell::Empty,
+        })
+    }
+}
 
-    while let Some(point) = iter.next() {
-        if point.is_empty() {
-            break;
+impl Snapshot for Fold {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Fold::Y(along) => (b'y', along).encode(out),
+            Fold::X(along) => (b'x', along).encode(out),
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match <(u8, usize)>::decode(input)? {
+            (b'y', along) => Some(Fold::Y(along)),
+            (b'x', along) => Some(Fold::X(along)),
+            _ => None,
         }
-        let mut coord = point.split(',');
-        let (col, row): (usize, usize) = (
-            coord.next().unwrap().parse().unwrap(),
-            coord.next().unwrap().parse().unwrap(),
-        );
-        matrix[row][col] = Cell::Full;
-    }
-
-    while let Some(line) = iter.next() {
-        let line = line.replace("fold along", "");
-        let mut parts = line.trim().split('=');
-        let fold = match (parts.next().unwrap(), parts.next().unwrap()) {
-            ("y", val) => Fold::Y(val.parse().unwrap()),
-            ("x", val) => Fold::X(val.parse().unwrap()),
-            _ => panic!("invalid fold input"),
-        };
-        folds.push(fold);
     }
-    (matrix, folds)
 }
 
-fn find_size(input: &str) -> (usize, usize) {
-    let (mut row_size, mut col_size) = (0, 0);
-    for line in input.lines() {
-        if line.is_empty() {
+#[aoc_generator(day13)]
+pub fn input_generator(input: &str) -> Result<(Grid<Cell>, Vec<Fold>), ParseError> {
+    let mut points = Vec::new();
+    let mut folds = Vec::new();
+
+    let mut iter = lines(13, input);
+
+    for line in iter.by_ref() {
+        if line.text.is_empty() {
             break;
         }
-        let mut coord = line.split(',');
-        let (col, row) = (
-            coord.next().unwrap().parse().unwrap(),
-            coord.next().unwrap().parse().unwrap(),
-        );
-        row_size = row_size.max(row);
-        col_size = col_size.max(col);
+        let (col, row) = line.split_once(line.text, ",")?;
+        let (col, row): (usize, usize) = (line.parse(col)?, line.parse(row)?);
+        points.push((row, col));
+    }
+    if points.is_empty() {
+        return Err(ParseError::eof(13, input, "no dots"));
+    }
+    let m = points.iter().map(|&(row, _)| row).max().unwrap() + 1;
+    let n = points.iter().map(|&(_, col)| col).max().unwrap() + 1;
+    let mut matrix = Grid::filled(n, m, Cell::Empty);
+    for pos in points {
+        matrix[pos] = Cell::Full;
     }
-    (row_size + 1, col_size + 1)
-}
 
-#[allow(dead_code)]
-fn get_table(grid: &[Vec<Cell>]) -> Table {
-    let mut table = Table::new();
-    for row in grid {
-        let formatted: Vec<_> = row.iter().map(|n| TableCell::new(&n.to_string())).collect();
-        table.add_row(Row::new(formatted));
+    // track the folded size so that out of bounds folds are reported here instead of panicking
+    let (mut height, mut width) = (m, n);
+    for line in iter {
+        let fold = line.strip_prefix(line.text, "fold along ")?;
+        let (axis, val) = line.split_once(fold, "=")?;
+        let along: usize = line.parse(val)?;
+        let size = match axis {
+            "y" => &mut height,
+            "x" => &mut width,
+            _ => return Err(line.error_at(axis, format!("invalid fold axis `{}`", axis))),
+        };
+        if along >= *size {
+            return Err(line.error_at(val, format!("fold line {} is out of bounds", along)));
+        }
+        *size = along.max(*size - 1 - along);
+        folds.push(if axis == "y" {
+            Fold::Y(along)
+        } else {
+            Fold::X(along)
+        });
+    }
+    if folds.is_empty() {
+        return Err(ParseError::eof(13, input, "no fold instructions"));
     }
-    table
+    Ok((matrix, folds))
 }
 
-fn fold(matrix: &[Vec<Cell>], fold: Fold) -> Vec<Vec<Cell>> {
+fn fold(matrix: &Grid<Cell>, fold: Fold) -> Grid<Cell> {
     match fold {
         Fold::Y(along) => fold_y(matrix, along),
         Fold::X(along) => fold_x(matrix, along),
     }
 }
 
-fn fold_y(matrix: &[Vec<Cell>], along: usize) -> Vec<Vec<Cell>> {
-    let (m, n) = (matrix.len(), matrix[0].len());
-    let (before, after) = (along, m - 1 - along);
-    let new_m = before.max(after);
-    let overlap_size = before.min(after);
-
-    let mut out = vec![vec![Cell::Empty; n]; new_m];
+/// Indices on either side of the fold line that land on index `k` of the folded dimension. The
+/// longer side decides the new length, so one of them may fall outside the original.
+fn folded(len: usize, along: usize, k: usize) -> impl Iterator<Item = usize> {
+    let distance = along.max(len - 1 - along) - k;
+    [along.checked_sub(distance), Some(along + distance)]
+        .into_iter()
+        .flatten()
+        .filter(move |&i| i < len)
+}
 
-    let no_overlap: Vec<_> = if new_m == before {
-        (0..before - after).collect()
+fn merge<I: IntoIterator<Item = Cell>>(cells: I) -> Cell {
+    if cells.into_iter().any(|c| c == Cell::Full) {
+        Cell::Full
     } else {
-        (m - (after - before)..m).rev().collect()
-    };
-
-    let mut row = 0;
-    for &rc in &no_overlap {
-        out[row] = matrix[rc].clone();
-        row += 1
-    }
-
-    let overlap = (along - overlap_size..along).zip((along + 1..along + 1 + overlap_size).rev());
-    for (br, ar) in overlap {
-        for col in 0..n {
-            if matrix[br][col] == Cell::Full || matrix[ar][col] == Cell::Full {
-                out[row][col] = Cell::Full;
-            }
-        }
-        row += 1;
+        Cell::Empty
     }
-    out
 }
 
-fn fold_x(matrix: &[Vec<Cell>], along: usize) -> Vec<Vec<Cell>> {
-    let (m, n) = (matrix.len(), matrix[0].len());
-    let (before, after) = (along, n - 1 - along);
-    let new_n = before.max(after);
-    let overlap_size = before.min(after);
+fn fold_y(matrix: &Grid<Cell>, along: usize) -> Grid<Cell> {
+    let (m, n) = (matrix.height(), matrix.width());
+    let new_m = along.max(m - 1 - along);
+    Grid::from_fn(n, new_m, |(row, col)| {
+        merge(folded(m, along, row).map(|r| matrix[(r, col)]))
+    })
+}
 
-    let mut out = vec![vec![Cell::Empty; new_n]; m];
+fn fold_x(matrix: &Grid<Cell>, along: usize) -> Grid<Cell> {
+    let (m, n) = (matrix.height(), matrix.width());
+    let new_n = along.max(n - 1 - along);
+    Grid::from_fn(new_n, m, |(row, col)| {
+        merge(folded(n, along, col).map(|c| matrix[(row, c)]))
+    })
+}
 
-    let no_overlap: Vec<_> = if new_n == before {
-        (0..before - after).collect()
-    } else {
-        (n - (after - before)..n).rev().collect()
-    };
+/// Glyphs of the 4x6 font the code is drawn with, row by row
+pub(crate) const FONT: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
 
-    let mut col = 0;
-    for &cc in &no_overlap {
-        for row in 0..m {
-            out[row][col] = matrix[row][cc];
-        }
-        col += 1
-    }
+/// Reads the code off the folded paper, one glyph every 5 columns. Unknown glyphs become `?`.
+fn read_letters(grid: &Grid<Cell>) -> String {
+    (0..grid.width().div_ceil(5))
+        .map(|i| {
+            let glyph: String = (0..6)
+                .flat_map(|row| (0..4).map(move |col| (row, i * 5 + col)))
+                .map(|(row, col)| match grid.get(row, col) {
+                    Some(Cell::Full) => '#',
+                    _ => '.',
+                })
+                .collect();
+            FONT.iter()
+                .find(|(_, g)| *g == glyph)
+                .map_or('?', |&(c, _)| c)
+        })
+        .collect()
+}
 
-    let overlap = (along - overlap_size..along).zip((along + 1..along + 1 + overlap_size).rev());
-    for (bc, ac) in overlap {
-        for row in 0..m {
-            if matrix[row][bc] == Cell::Full || matrix[row][ac] == Cell::Full {
-                out[row][col] = Cell::Full;
+/// The sheet before and after every fold, ending with the code it reveals
+pub(crate) fn frames((paper, folds): (Grid<Cell>, Vec<Fold>)) -> Frames {
+    let mut sheet = Some(paper);
+    let mut folds = folds.into_iter();
+    let mut title = "unfolded".to_string();
+    Box::new(std::iter::from_fn(move || {
+        let current = sheet.take()?;
+        let dots = current.iter().filter(|&&c| c == Cell::Full).count();
+        let mut frame = format!("{}, {} dots", title, dots);
+        match folds.next() {
+            Some(next) => {
+                title = match next {
+                    Fold::X(along) => format!("fold along x={}", along),
+                    Fold::Y(along) => format!("fold along y={}", along),
+                };
+                sheet = Some(fold(&current, next));
             }
+            None => frame += &format!(", code {}", read_letters(&current)),
         }
-        col += 1;
-    }
-    out
+        let picture = viz::picture(current.width(), current.height(), |pos| {
+            current[pos] == Cell::Full
+        });
+        Some(frame + "\n" + &picture)
+    }))
 }
 
 #[aoc(day13, part1, d131)]
-pub fn part1(input: &(Vec<Vec<Cell>>, Vec<Fold>)) -> u64 {
+pub fn part1(input: &(Grid<Cell>, Vec<Fold>)) -> u64 {
     let (matrix, folds) = input;
     let res = fold(matrix, folds[0]);
-    res.into_iter()
-        .flatten()
-        .filter(|c| c == &Cell::Full)
-        .count() as u64
+    res.iter().filter(|&&c| c == Cell::Full).count() as u64
 }
 
 #[aoc(day13, part2, d132)]
-pub fn part2(input: &(Vec<Vec<Cell>>, Vec<Fold>)) -> String {
-    let (matrix, folds) = input;
-    let mut res: Vec<Vec<Cell>> = matrix.to_owned();
+pub fn part2(input: &(Grid<Cell>, Vec<Fold>)) -> String {
+    read_letters(&fold_all(input))
+}
+
+fn fold_all((matrix, folds): &(Grid<Cell>, Vec<Fold>)) -> Grid<Cell> {
+    let mut res = matrix.clone();
     for f in folds {
         res = fold(&res, *f);
     }
-    let mut table = get_table(&res);
-    table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
-    // NOTICE: Uncomment below to print the table to stdout and see the password
-    //table.printstd();
-    "CJCKBAPB".to_string()
+    res
+}
+
+/// The sheet once folded, showing the code
+pub(crate) fn images(input: &(Grid<Cell>, Vec<Fold>)) -> Vec<(&'static str, Image)> {
+    let folded = Image::bitmap(&fold_all(input), |&cell| cell == Cell::Full);
+    vec![("folded", folded)]
 }
 
 #[cfg(test)]
@@ -206,7 +267,45 @@ fold along x=5";
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 17);
     }
+
+    /// Draws `code` with dots mirrored below a horizontal fold, as the puzzle input does
+    fn draw(code: &str) -> String {
+        let mut dots = Vec::new();
+        for (i, letter) in code.chars().enumerate() {
+            let (_, glyph) = FONT.iter().find(|(c, _)| *c == letter).unwrap();
+            for (j, _) in glyph.char_indices().filter(|&(_, c)| c == '#') {
+                dots.push(format!("{},{}", i * 5 + j % 4, 12 - j / 4));
+            }
+        }
+        dots.join("\n") + "\n\nfold along y=6"
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = input_generator(&draw("CJCKBAPB")).unwrap();
+        assert_eq!(part2(&input), "CJCKBAPB");
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), "?");
+    }
+
+    #[test]
+    fn test_fold() {
+        let (matrix, folds) = input_generator(SAMPLE).unwrap();
+        let folded = folds.iter().fold(matrix, |acc, &f| fold(&acc, f));
+        let expected = "#####\n#   #\n#   #\n#   #\n#####\n     \n     ";
+        assert_eq!(folded.to_string(), expected);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let err = input_generator("1,2\n\nfold along z=1").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 12));
+        let err = input_generator("1,2\n3,4\n\nfold along y=7").unwrap_err();
+        assert_eq!(err.reason, "fold line 7 is out of bounds");
+        let err = input_generator("1,2\n3,4").unwrap_err();
+        assert_eq!(err.reason, "no fold instructions");
+    }
 }
@@ -1,60 +1,66 @@
+use crate::parse::{lines, ParseError};
+use crate::simulation::Simulation;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[aoc_generator(day14)]
-pub fn input_generator(input: &str) -> (String, HashMap<[char; 2], char>) {
-    let mut iter = input.lines();
-    let start_string = iter.next().unwrap().to_string();
+pub fn input_generator(input: &str) -> Result<(String, Rules), ParseError> {
+    let mut iter = lines(14, input);
+    let template = iter
+        .next()
+        .ok_or_else(|| ParseError::eof(14, input, "missing polymer template"))?;
+    if template.text.len() < 2 {
+        return Err(template.error("polymer template needs at least two elements"));
+    }
+    let start_string = template.text.to_string();
     let mut map = HashMap::new();
     iter.next();
-    while let Some(line) = iter.next() {
-        let line = line.replace(" -> ", "");
-        let chars: Vec<_> = line.chars().collect();
-        let key = [chars[0], chars[1]];
-        map.insert(key, chars[2]);
+    for line in iter {
+        let (pair, insert) = line.split_once(line.text, " -> ")?;
+        let (pair, insert): (Vec<_>, Vec<_>) = (pair.chars().collect(), insert.chars().collect());
+        if pair.len() != 2 || insert.len() != 1 {
+            return Err(line.error("expected a rule like `AB -> C`"));
+        }
+        map.insert([pair[0], pair[1]], insert[0]);
+    }
+
+    // every pair that can show up while growing the polymer needs a rule
+    let elements: HashSet<_> = start_string.chars().chain(map.values().copied()).collect();
+    for &left in &elements {
+        for &right in &elements {
+            if !map.contains_key(&[left, right]) {
+                let reason = format!("missing insertion rule for `{}{}`", left, right);
+                return Err(ParseError::eof(14, input, reason));
+            }
+        }
     }
-    (start_string, map)
+    Ok((start_string, map))
 }
 
-struct Simulator {
-    counts: HashMap<[char; 2], u64>,
+pub type Rules = HashMap<[char; 2], char>;
+
+/// Polymer tracked as the number of occurrences of every pair of adjacent elements
+#[derive(Debug, Clone)]
+struct Polymer<'a> {
+    counts: BTreeMap<[char; 2], u64>,
     last_letter: char,
+    rules: &'a Rules,
 }
 
-impl Simulator {
-    fn from_str(input: &str) -> Self {
-        let chars: Vec<_> = input.chars().collect();
-        let mut counts = HashMap::new();
-        for i in 1..chars.len() {
-            *counts.entry([chars[i - 1], chars[i]]).or_insert(0) += 1;
+impl<'a> Polymer<'a> {
+    fn new(template: &str, rules: &'a Rules) -> Self {
+        let chars: Vec<_> = template.chars().collect();
+        let mut counts = BTreeMap::new();
+        for pair in chars.windows(2) {
+            *counts.entry([pair[0], pair[1]]).or_insert(0) += 1;
         }
         Self {
             counts,
             last_letter: *chars.last().unwrap(),
+            rules,
         }
     }
 
-    fn simluate(&mut self, rules: &HashMap<[char; 2], char>, rounds: usize) -> (u64, u64) {
-        for _ in 0..rounds {
-            self.tick(rules);
-        }
-        self.min_max()
-    }
-
-    fn tick(&mut self, rules: &HashMap<[char; 2], char>) {
-        let mut new_counts =
-            self.counts
-                .iter()
-                .fold(HashMap::new(), |mut acc, ([left, right], count)| {
-                    let (left, right) = (*left, *right);
-                    let between = *rules.get(&[left, right]).unwrap();
-                    *acc.entry([left, between]).or_insert(0) += count;
-                    *acc.entry([between, right]).or_insert(0) += count;
-                    acc
-                });
-        std::mem::swap(&mut self.counts, &mut new_counts);
-    }
-
     fn char_occurence(&self) -> HashMap<char, u64> {
         let mut occ = self
             .counts
@@ -79,27 +85,40 @@ impl Simulator {
     }
 }
 
-#[aoc(day14, part1, day14_1)]
-pub fn part1(input: &(String, HashMap<[char; 2], char>)) -> u64 {
-    let (start_str, rules) = (input.0.as_str(), &input.1);
+impl Simulation for Polymer<'_> {
+    fn step(&mut self) {
+        let mut new_counts = BTreeMap::new();
+        for (&[left, right], count) in &self.counts {
+            let between = self.rules[&[left, right]];
+            *new_counts.entry([left, between]).or_insert(0) += count;
+            *new_counts.entry([between, right]).or_insert(0) += count;
+        }
+        self.counts = new_counts;
+    }
+}
 
-    let mut sim = Simulator::from_str(start_str);
-    let (min, max) = sim.simluate(rules, 10);
+/// Difference between the most and least common elements after `steps` insertion steps
+pub fn spread_after(input: &(String, Rules), steps: usize) -> u64 {
+    let mut polymer = Polymer::new(&input.0, &input.1);
+    polymer.run(steps);
+    let (min, max) = polymer.min_max();
     max - min
 }
 
-#[aoc(day14, part2, day14_2)]
-pub fn part2(input: &(String, HashMap<[char; 2], char>)) -> u64 {
-    let (start_str, rules) = (input.0.as_str(), &input.1);
+#[aoc(day14, part1, day14_1)]
+pub fn part1(input: &(String, Rules)) -> u64 {
+    spread_after(input, 10)
+}
 
-    let mut sim = Simulator::from_str(start_str);
-    let (min, max) = sim.simluate(rules, 40);
-    max - min
+#[aoc(day14, part2, day14_2)]
+pub fn part2(input: &(String, Rules)) -> u64 {
+    spread_after(input, 40)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth::Rng;
     const SAMPLE: &str = "NNCB
 
 CH -> B
@@ -119,15 +138,53 @@ BC -> B
 CC -> N
 CN -> C";
 
+    /// Grows the polymer as an actual string
+    fn brute_force(input: &(String, Rules), steps: usize) -> u64 {
+        let (template, rules) = input;
+        let mut polymer: Vec<char> = template.chars().collect();
+        for _ in 0..steps {
+            let mut grown = vec![polymer[0]];
+            for pair in polymer.windows(2) {
+                grown.extend([rules[&[pair[0], pair[1]]], pair[1]]);
+            }
+            polymer = grown;
+        }
+        let mut counts = HashMap::new();
+        for element in polymer {
+            *counts.entry(element).or_insert(0) += 1;
+        }
+        counts.values().max().unwrap() - counts.values().min().unwrap()
+    }
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(spread_after(&input, 0), 1);
         assert_eq!(part1(&input), 1588);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 2188189693529);
     }
+
+    #[test]
+    fn test_brute_force() {
+        let mut rng = Rng::new(14);
+        for _ in 0..100 {
+            let elements = &['B', 'C', 'H', 'N'][..rng.range(1..=4) as usize];
+            let template: String = (0..rng.range(2..=5)).map(|_| *rng.pick(elements)).collect();
+            let mut rules = Vec::new();
+            for &a in elements {
+                for &b in elements {
+                    rules.push(format!("{}{} -> {}", a, b, rng.pick(elements)));
+                }
+            }
+            let input = input_generator(&format!("{}\n\n{}", template, rules.join("\n")));
+            let input = input.unwrap();
+            let steps = rng.below(11);
+            assert_eq!(spread_after(&input, steps), brute_force(&input, steps));
+        }
+    }
 }
@@ -1,16 +1,18 @@
+use crate::export::Image;
+use crate::grid::Grid;
+use crate::parse::{lines, ParseError};
+use crate::viz::Frames;
 use aoc_runner_derive::{aoc, aoc_generator};
+use colored::*;
 use std::collections::BinaryHeap;
 
 #[aoc_generator(day15)]
-pub fn input_generator(input: &str) -> Vec<Vec<u64>> {
-    input.lines().fold(Vec::new(), |mut acc, line| {
-        let row = line
-            .chars()
-            .map(|n| n.to_digit(10).unwrap() as u64)
-            .collect();
-        acc.push(row);
-        acc
-    })
+pub fn input_generator(input: &str) -> Result<Grid<u8>, ParseError> {
+    let grid = Grid::from_digits(lines(15, input))?;
+    if grid.is_empty() {
+        return Err(ParseError::eof(15, input, "empty risk map"));
+    }
+    Ok(grid)
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,35 +24,29 @@ struct Position {
 }
 
 impl Position {
-    fn new(grid: &[Vec<u64>], row: usize, col: usize) -> Self {
+    fn new(grid: &Grid<u8>, row: usize, col: usize) -> Self {
         Self {
             row,
             col,
-            weight: grid[row][col],
+            weight: grid[(row, col)] as u64,
             next: None,
         }
     }
 
-    fn end(grid: &[Vec<u64>]) -> Self {
-        let (m, n) = (grid.len(), grid[0].len());
-        Self::new(grid, m - 1, n - 1)
+    fn end(grid: &Grid<u8>) -> Self {
+        Self::new(grid, grid.height() - 1, grid.width() - 1)
     }
 
-    fn from_next(&self, grid: &[Vec<u64>], row: usize, col: usize) -> Self {
+    fn step_to(&self, grid: &Grid<u8>, row: usize, col: usize) -> Self {
         let mut out = Self::new(grid, row, col);
         out.weight += self.weight;
         out.next = Some((self.row, self.col));
         out
     }
 
-    fn neighboors(&self, grid: &[Vec<u64>]) -> Vec<Position> {
-        let (m, n) = (grid.len() as i32, grid[0].len() as i32);
-        let (r, c) = (self.row as i32, self.col as i32);
-        [(-1, 0), (1, 0), (0, 1), (0, -1)]
-            .into_iter()
-            .map(|(or, oc)| (or + r, oc + c))
-            .filter(|(or, oc)| or >= &0 && or < &m && oc >= &0 && oc < &n)
-            .map(|(or, oc)| self.from_next(grid, or as usize, oc as usize))
+    fn neighboors(&self, grid: &Grid<u8>) -> Vec<Position> {
+        grid.neighbours4((self.row, self.col))
+            .map(|(row, col)| self.step_to(grid, row, col))
             .collect()
     }
 
@@ -61,21 +57,22 @@ impl Position {
 
 impl Ord for Position {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        other.weight.cmp(&self.weight)
     }
 }
 
 impl PartialOrd for Position {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.weight.partial_cmp(&self.weight)
+        Some(self.cmp(other))
     }
 }
 
-fn shortest_path(grid: &[Vec<u64>]) -> u64 {
-    let (m, n) = (grid.len(), grid[0].len());
-
+/// Searches from the end back to the start, so the `next` of every settled position leads to the
+/// end along a path of lowest risk
+fn search(grid: &Grid<u8>) -> (Position, Grid<Option<(usize, usize)>>) {
     let mut pq: BinaryHeap<Position> = BinaryHeap::new();
-    let mut visited = vec![vec![false; n]; m];
+    let mut next = Grid::filled(grid.width(), grid.height(), None);
+    let mut visited = Grid::filled(grid.width(), grid.height(), false);
     pq.push(Position::end(grid));
 
     let start = loop {
@@ -83,58 +80,92 @@ fn shortest_path(grid: &[Vec<u64>]) -> u64 {
         if pos.is_start() {
             break pos;
         }
-        if visited[pos.row][pos.col] {
+        if visited[(pos.row, pos.col)] {
             continue;
         }
-        visited[pos.row][pos.col] = true;
+        visited[(pos.row, pos.col)] = true;
+        next[(pos.row, pos.col)] = pos.next;
         pos.neighboors(grid).into_iter().for_each(|n| pq.push(n));
     };
-    start.weight - grid[0][0]
+    (start, next)
 }
 
-#[aoc(day15, part1, day15_1)]
-pub fn part1(grid: &Vec<Vec<u64>>) -> u64 {
-    shortest_path(grid)
+fn shortest_path(grid: &Grid<u8>) -> u64 {
+    let (start, _) = search(grid);
+    start.weight - grid[(0, 0)] as u64
 }
 
-fn extend_grid(grid: &Vec<Vec<u64>>, size: u64) -> Vec<Vec<u64>> {
-    let mut map = grid.clone();
-    // extend vertically
-    for offset in 1..size {
-        let mut new_block = grid.clone();
-        new_block.iter_mut().for_each(|row| {
-            row.iter_mut().for_each(|cell| {
-                if *cell + offset <= 9 {
-                    *cell += offset
-                } else {
-                    *cell = (*cell + offset) % 9;
-                }
-            })
-        });
-        for row in new_block {
-            map.push(row);
-        }
+/// Positions of a path of lowest risk from the top left to the bottom right corner
+fn safest_path(grid: &Grid<u8>) -> Vec<(usize, usize)> {
+    let (start, next) = search(grid);
+    let mut path = vec![(0, 0)];
+    let mut current = start.next;
+    while let Some(pos) = current {
+        path.push(pos);
+        current = next[pos];
     }
-    // extend horizontally
-    for row in 0..map.len() {
-        let template = map[row].clone();
-        for offset in 1..size {
-            let mut new_seg = template.clone();
-            new_seg.iter_mut().for_each(|cell| {
-                if *cell + offset <= 9 {
-                    *cell += offset
-                } else {
-                    *cell = (*cell + offset) % 9;
-                }
-            });
-            map[row].append(&mut new_seg);
-        }
+    path
+}
+
+/// The path of lowest risk of part 1 in red over the risk map in shades of gray
+pub(crate) fn images(grid: &Grid<u8>) -> Vec<(&'static str, Image)> {
+    let mut on_path = grid.map(|&risk| [risk * 28; 3]);
+    for pos in safest_path(grid) {
+        on_path[pos] = [255, 0, 0];
     }
-    map
+    vec![("path", Image::pixmap(&on_path, |&color| color))]
+}
+
+/// The path of lowest risk of part 1 drawn over the risk map a bit at a time
+pub(crate) fn frames(grid: Grid<u8>) -> Frames {
+    let path = safest_path(&grid);
+    let chunk = path.len().div_ceil(60);
+    let mut shown = (0..path.len()).step_by(chunk).chain([path.len()]);
+    Box::new(std::iter::from_fn(move || {
+        let shown = shown.next()?;
+        let mut on_path = Grid::filled(grid.width(), grid.height(), false);
+        for &pos in &path[..shown] {
+            on_path[pos] = true;
+        }
+        let risk: u64 = path[1..shown.max(1)]
+            .iter()
+            .map(|&pos| grid[pos] as u64)
+            .sum();
+        let map = Grid::from_fn(grid.width(), grid.height(), |pos| {
+            let risk = grid[pos].to_string();
+            if on_path[pos] {
+                risk.bright_green().bold()
+            } else {
+                risk.dimmed()
+            }
+        });
+        Some(format!(
+            "{} of {} steps, risk {}\n{}",
+            shown,
+            path.len(),
+            risk,
+            map
+        ))
+    }))
+}
+
+#[aoc(day15, part1, day15_1)]
+pub fn part1(grid: &Grid<u8>) -> u64 {
+    shortest_path(grid)
+}
+
+/// Tiles the grid `size` times in both directions, increasing the risk by one per tile away
+/// from the original and wrapping back to 1 after 9
+fn extend_grid(grid: &Grid<u8>, size: usize) -> Grid<u8> {
+    let (m, n) = (grid.height(), grid.width());
+    Grid::from_fn(n * size, m * size, |(row, col)| {
+        let offset = (row / m + col / n) as u8;
+        (grid[(row % m, col % n)] + offset - 1) % 9 + 1
+    })
 }
 
 #[aoc(day15, part2, day15_2)]
-pub fn part2(grid: &Vec<Vec<u64>>) -> u64 {
+pub fn part2(grid: &Grid<u8>) -> u64 {
     let map = extend_grid(grid, 5);
     shortest_path(&map)
 }
@@ -155,13 +186,17 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 40);
+        let path = safest_path(&input);
+        assert_eq!((path[0], path[path.len() - 1]), ((0, 0), (9, 9)));
+        let risk: u64 = path[1..].iter().map(|&pos| input[pos] as u64).sum();
+        assert_eq!(risk, 40);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 315);
     }
 }
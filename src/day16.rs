@@ -1,3 +1,5 @@
+use crate::parse::{lines, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::VecDeque;
 
@@ -30,12 +32,14 @@ impl BitStream {
         self.bit_stream.len() * 8 + self.remainder.len()
     }
 
-    fn read(&mut self, amount: u8) -> u32 {
+    fn read(&mut self, amount: u8) -> Result<u32, String> {
         assert!(amount <= 32, "cannot read more than 32 bits at a time");
-        assert!(
-            amount as usize <= self.len(),
-            "amount is greater than stream length"
-        );
+        if amount as usize > self.len() {
+            return Err(format!(
+                "expected {} more bits but the transmission ended",
+                amount
+            ));
+        }
         let mut acc = 0;
         for _ in 0..amount {
             if self.remainder.is_empty() {
@@ -47,12 +51,12 @@ impl BitStream {
 
             acc = (acc << 1) | (self.remainder.pop().unwrap() as u32)
         }
-        acc
+        Ok(acc)
     }
 }
 
 #[derive(Debug)]
-struct Packet {
+pub struct Packet {
     version: u8,
     packet_type: Type,
 }
@@ -74,6 +78,46 @@ enum Op {
     Equal,
 }
 
+impl Snapshot for Packet {
+    /// The type id of the packet followed by its value or its sub-packets
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.version.encode(out);
+        match &self.packet_type {
+            Type::Literal(val) => (4_u8, *val).encode(out),
+            Type::Operator { children, op } => {
+                let id: u8 = match op {
+                    Op::Sum => 0,
+                    Op::Product => 1,
+                    Op::Min => 2,
+                    Op::Max => 3,
+                    Op::Greater => 5,
+                    Op::Less => 6,
+                    Op::Equal => 7,
+                };
+                id.encode(out);
+                children.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let version = u8::decode(input)?;
+        let op = match u8::decode(input)? {
+            4 => return Some(Self::new(version, Type::Literal(u64::decode(input)?))),
+            0 => Op::Sum,
+            1 => Op::Product,
+            2 => Op::Min,
+            3 => Op::Max,
+            5 => Op::Greater,
+            6 => Op::Less,
+            7 => Op::Equal,
+            _ => return None,
+        };
+        let children = Vec::decode(input)?;
+        Some(Self::new(version, Type::Operator { children, op }))
+    }
+}
+
 impl Packet {
     fn new(version: u8, packet_type: Type) -> Self {
         Self {
@@ -81,13 +125,13 @@ impl Packet {
             packet_type,
         }
     }
-    fn from_stream(bits: &mut BitStream) -> Self {
-        let version = bits.read(3) as u8;
-        let id = bits.read(3) as u8;
+    fn from_stream(bits: &mut BitStream) -> Result<Self, String> {
+        let version = bits.read(3)? as u8;
+        let id = bits.read(3)? as u8;
         if id == 4 {
-            let mut acc = 0 as u64;
+            let mut acc = 0_u64;
             loop {
-                let next_bits = bits.read(5) as u64;
+                let next_bits = bits.read(5)? as u64;
                 let is_last = next_bits & (1 << 4) == 0;
                 for pos in (0..4).rev() {
                     if (next_bits & (1 << pos)) != 0 {
@@ -97,30 +141,32 @@ impl Packet {
                     }
                 }
                 if is_last {
-                    return Self::new(version, Type::Literal(acc));
+                    return Ok(Self::new(version, Type::Literal(acc)));
                 }
             }
         }
 
         let mut children = Vec::new();
-        let len_type_id = bits.read(1);
+        let len_type_id = bits.read(1)?;
 
         if len_type_id == 0 {
-            let children_len = bits.read(15) as usize;
+            let children_len = bits.read(15)? as usize;
             let stream_len_before = bits.len();
             'outer: loop {
                 let bits_read = stream_len_before - bits.len();
                 match bits_read.cmp(&children_len) {
                     std::cmp::Ordering::Equal => break 'outer,
-                    std::cmp::Ordering::Greater => panic!("invalid packet"),
+                    std::cmp::Ordering::Greater => {
+                        return Err("sub-packets overran their declared length".to_string())
+                    }
                     _ => {}
                 }
-                children.push(Self::from_stream(bits));
+                children.push(Self::from_stream(bits)?);
             }
         } else {
-            let num_children = bits.read(11) as usize;
+            let num_children = bits.read(11)? as usize;
             for _ in 0..num_children {
-                children.push(Self::from_stream(bits));
+                children.push(Self::from_stream(bits)?);
             }
         }
 
@@ -132,10 +178,16 @@ impl Packet {
             5 => Op::Greater,
             6 => Op::Less,
             7 => Op::Equal,
-            _ => panic!("invalid op"),
+            _ => unreachable!("type ids only have 3 bits"),
         };
+        if matches!(op, Op::Greater | Op::Less | Op::Equal) && children.len() != 2 {
+            return Err(format!(
+                "comparison packet needs 2 sub-packets, found {}",
+                children.len()
+            ));
+        }
 
-        return Self::new(version, Type::Operator { children, op });
+        Ok(Self::new(version, Type::Operator { children, op }))
     }
 
     fn version_sum(&self) -> u64 {
@@ -174,21 +226,30 @@ impl Packet {
 }
 
 #[aoc_generator(day16)]
-pub fn input_generator(input: &str) -> BitStream {
-    BitStream::from_hex(input).expect("input should be valid")
+pub fn input_generator(input: &str) -> Result<Packet, ParseError> {
+    let line = lines(16, input)
+        .next()
+        .ok_or_else(|| ParseError::eof(16, input, "missing transmission"))?;
+    let hex = line.text.trim();
+    if let Some(i) = hex.find(|c: char| !c.is_ascii_hexdigit()) {
+        return Err(line.error_at(&hex[i..], "expected a hexadecimal digit"));
+    }
+    let mut bits = BitStream::from_hex(hex).unwrap();
+    let total = bits.len();
+    Packet::from_stream(&mut bits).map_err(|reason| {
+        // point at the hex digit holding the bit where decoding stopped
+        let digit = ((total - bits.len()) / 4).min(hex.len());
+        line.error_at(&hex[digit..], reason)
+    })
 }
 
 #[aoc(day16, part1, day16_1)]
-pub fn part1(bits: &BitStream) -> u64 {
-    let mut bits = bits.clone();
-    let packet = Packet::from_stream(&mut bits);
+pub fn part1(packet: &Packet) -> u64 {
     packet.version_sum()
 }
 
 #[aoc(day16, part2, day16_2)]
-pub fn part2(bits: &BitStream) -> u64 {
-    let mut bits = bits.clone();
-    let packet = Packet::from_stream(&mut bits);
+pub fn part2(packet: &Packet) -> u64 {
     packet.value()
 }
 
@@ -218,7 +279,7 @@ mod tests {
     #[test]
     fn test_part_1() {
         for (hex, expected) in SAMPLES_1 {
-            let input = input_generator(hex);
+            let input = input_generator(hex).unwrap();
             assert_eq!(part1(&input), expected)
         }
     }
@@ -226,8 +287,19 @@ mod tests {
     #[test]
     fn test_part_2() {
         for (hex, expected) in SAMPLES_2 {
-            let input = input_generator(hex);
+            let input = input_generator(hex).unwrap();
             assert_eq!(part2(&input), expected)
         }
     }
+
+    #[test]
+    fn test_invalid_input() {
+        let err = input_generator("D2FE2G").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 6));
+        let err = input_generator("D2FE").unwrap_err();
+        assert_eq!(
+            err.reason,
+            "expected 5 more bits but the transmission ended"
+        );
+    }
 }
@@ -1,5 +1,8 @@
-/// A lot of code was added (and not removed) since I assumed target  could be in the negative x
-/// direction.  I removed some of the code but left the rest
+// A lot of code was added (and not removed) since I assumed target  could be in the negative x
+// direction.  I removed some of the code but left the rest
+use crate::limits;
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::ops::RangeInclusive;
 
@@ -12,6 +15,25 @@ pub struct Target {
     y_range: RangeInclusive<i64>,
 }
 
+impl Snapshot for Target {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.near_x, self.far_x, self.bottom_y).encode(out);
+        self.y_range.end().encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (near_x, far_x, bottom_y) = Snapshot::decode(input)?;
+        let top_y = i64::decode(input)?;
+        Some(Target {
+            near_x,
+            far_x,
+            bottom_y,
+            x_range: near_x.min(far_x)..=near_x.max(far_x),
+            y_range: bottom_y..=top_y,
+        })
+    }
+}
+
 #[derive(Default)]
 struct Vector {
     x: i64,
@@ -91,19 +113,25 @@ fn quadratic(num: i64) -> (i64, i64) {
     (a.min(b), a.max(b))
 }
 
-fn to_tuple(input: &str) -> (i64, i64) {
-    let input = &input[2..];
-    let mut parts = input.split("..").map(|n| n.parse::<i64>().unwrap());
-    let (a, b) = (parts.next().unwrap(), parts.next().unwrap());
-    (a.min(b), b.max(a))
+fn to_tuple(line: &Line, input: &str, axis: &str) -> Result<(i64, i64), ParseError> {
+    let input = line.strip_prefix(input, axis)?;
+    let (a, b) = line.split_once(input, "..")?;
+    let (a, b): (i64, i64) = (line.parse(a)?, line.parse(b)?);
+    Ok((a.min(b), b.max(a)))
 }
 
 #[aoc_generator(day17)]
-pub fn input_generator(input: &str) -> Target {
-    let input = input.replace("target area: ", "");
-    let mut parts = input.split(", ");
-    let (x1, x2) = to_tuple(parts.next().unwrap());
-    let (y1, y2) = to_tuple(parts.next().unwrap());
+pub fn input_generator(input: &str) -> Result<Target, ParseError> {
+    let line = lines(17, input)
+        .next()
+        .ok_or_else(|| ParseError::eof(17, input, "missing target area"))?;
+    let area = line.strip_prefix(line.text.trim(), "target area: ")?;
+    let (x, y) = line.split_once(area, ", ")?;
+    let (x1, x2) = to_tuple(&line, x, "x=")?;
+    let (y1, y2) = to_tuple(&line, y, "y=")?;
+    if y2 >= 0 {
+        return Err(line.error_at(y, "target area must lie below the launcher"));
+    }
 
     let (near_x, far_x) = if x1.is_negative() {
         (x1.max(x2), x1.min(x2))
@@ -112,30 +140,26 @@ pub fn input_generator(input: &str) -> Target {
     };
     let (bottom_y, top_y) = (y1.min(y2), y1.max(y2));
 
-    Target {
+    Ok(Target {
         near_x,
         far_x,
         bottom_y,
         x_range: near_x.min(far_x)..=near_x.max(far_x),
         y_range: bottom_y..=top_y,
-    }
+    })
 }
 
-#[aoc(day17, part1, day17_1)]
-pub fn part1(target: &Target) -> u64 {
-    (target.bottom_y * (target.bottom_y + 1) / 2) as u64
-}
-
-#[aoc(day17, part2, day17_2)]
-pub fn part2(target: &Target) -> u64 {
-    let mut count = 0;
+/// Every initial velocity that puts the probe in the target after some step
+fn hits(target: &Target) -> Vec<Vector> {
+    let mut out = Vec::new();
     let possible_ys: Vec<_> = target.vel_y_range().collect();
     for x in target.vel_x_range() {
         for &y in &possible_ys {
             let mut probe = Probe::new(Vector { x, y });
             loop {
+                limits::step();
                 if probe.in_target(target) {
-                    count += 1;
+                    out.push(Vector { x, y });
                     break;
                 }
                 if probe.missed_target(target) {
@@ -145,23 +169,94 @@ pub fn part2(target: &Target) -> u64 {
             }
         }
     }
-    count
+    out
+}
+
+/// Whether some horizontal velocity runs out right above the target, so that the probe can
+/// fall into it from any height
+fn stalls_above(target: &Target) -> bool {
+    let sign = target.far_x.signum();
+    (0..=target.far_x.abs()).any(|v| target.x_range.contains(&(sign * v * (v + 1) / 2)))
+}
+
+#[aoc(day17, part1, day17_1)]
+pub fn part1(target: &Target) -> u64 {
+    // the probe comes back to y = 0 with its launch speed plus one, so the fastest launch that
+    // still falls into the target moves straight from 0 to the bottom row
+    if stalls_above(target) {
+        return (target.bottom_y * (target.bottom_y + 1) / 2) as u64;
+    }
+    hits(target)
+        .iter()
+        .map(|v| (v.y.max(0) * (v.y.max(0) + 1) / 2) as u64)
+        .max()
+        .unwrap_or(0)
+}
+
+#[aoc(day17, part2, day17_2)]
+pub fn part2(target: &Target) -> u64 {
+    hits(target).len() as u64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth::Rng;
     const SAMPLE: &str = "target area: x=20..30, y=-10..-5";
 
+    /// Highest point and number of hits over every launch that could possibly hit the target,
+    /// following each trajectory step by step
+    fn brute_force(x: RangeInclusive<i64>, y: RangeInclusive<i64>) -> (u64, u64) {
+        let (mut highest, mut count) = (0, 0);
+        let reach = x.start().abs().max(x.end().abs());
+        for vx in -reach..=reach {
+            for vy in *y.start()..=-*y.start() {
+                let (mut px, mut py, mut dx, mut dy, mut top) = (0, 0, vx, vy, 0);
+                while py >= *y.start() {
+                    if x.contains(&px) && y.contains(&py) {
+                        highest = highest.max(top);
+                        count += 1;
+                        break;
+                    }
+                    px += dx;
+                    py += dy;
+                    dx -= dx.signum();
+                    dy -= 1;
+                    top = top.max(py);
+                }
+            }
+        }
+        (highest as u64, count)
+    }
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 45)
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 112)
     }
+
+    #[test]
+    fn test_brute_force() {
+        let mut rng = Rng::new(17);
+        for _ in 0..100 {
+            let near = rng.range(1..=30);
+            let (mut x1, mut x2) = (near, near + rng.range(0..=10));
+            if rng.chance(1, 2) {
+                (x1, x2) = (-x2, -x1);
+            }
+            let y1 = rng.range(-25..=-1);
+            let y2 = rng.range(y1..=-1);
+            let input = format!("target area: x={}..{}, y={}..{}", x1, x2, y1, y2);
+            let target = input_generator(&input).unwrap();
+            let (highest, count) = brute_force(x1..=x2, y1..=y2);
+            assert_eq!(part1(&target), highest, "{}", input);
+            assert_eq!(part2(&target), count, "{}", input);
+        }
+    }
 }
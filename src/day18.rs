@@ -1,3 +1,5 @@
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
@@ -23,12 +25,23 @@ impl NodeContent {
 
 pub type SnailNumber = Rc<RefCell<NodeContent>>;
 
+/// Snailfish number as parsed. Unlike the linked nodes that get reduced in place, it can be shared
+/// between threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tree {
+    Leaf(u64),
+    Pair(Box<Tree>, Box<Tree>),
+}
+
 trait Node
 where
     Self: Sized,
 {
     fn new_node(data: Option<u64>) -> Self;
-    fn from_str(input: &str) -> Self;
+    fn from_line(line: &Line) -> Result<Self, ParseError>;
+    fn from_tree(tree: &Tree) -> Self;
+    fn to_tree(&self) -> Tree;
+    fn is_valid(&self) -> bool;
     fn insert_left(&mut self, data: Option<u64>);
     fn insert_right(&mut self, data: Option<u64>);
     fn left(&self) -> Option<Self>;
@@ -40,6 +53,7 @@ where
     fn reduce(&mut self) -> bool;
     fn is_leaf(&self) -> bool;
     fn add(self, other: Self) -> Self;
+    #[allow(dead_code)] // only used to check parsing round-trips
     fn to_string(&self) -> String;
     fn magnitude(&self) -> u64;
 }
@@ -49,31 +63,61 @@ impl Node for SnailNumber {
         Rc::new(RefCell::new(NodeContent::new(data)))
     }
 
-    fn from_str(input: &str) -> Self {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
         let root = Self::new_node(None);
         let mut cursor = root.clone();
-        let mut char_iter = input.chars();
-        while let Some(c) = char_iter.next() {
+        for (i, c) in line.text.char_indices() {
+            let unexpected = || line.error_at(&line.text[i..], format!("unexpected `{}`", c));
             match c {
                 '[' => {
                     cursor.insert_left(None);
                     cursor = cursor.left().unwrap();
                 }
                 ']' => {
-                    cursor = cursor.parent().unwrap();
+                    cursor = cursor.parent().ok_or_else(unexpected)?;
                 }
                 ',' => {
-                    cursor = cursor.parent().unwrap();
+                    cursor = cursor.parent().ok_or_else(unexpected)?;
                     cursor.insert_right(None);
                     cursor = cursor.right().unwrap();
                 }
                 val => {
-                    let val: u64 = val.to_string().parse().expect("invalid input");
-                    cursor.set_data(val)
+                    let val = val.to_digit(10).ok_or_else(unexpected)?;
+                    cursor.set_data(val as u64)
                 }
             }
         }
-        root
+        if !Rc::ptr_eq(&cursor, &root) || !root.is_valid() {
+            return Err(line.error("incomplete snailfish number"));
+        }
+        Ok(root)
+    }
+
+    fn from_tree(tree: &Tree) -> Self {
+        match tree {
+            Tree::Leaf(val) => Self::new_node(Some(*val)),
+            Tree::Pair(left, right) => Self::from_tree(left).add(Self::from_tree(right)),
+        }
+    }
+
+    fn to_tree(&self) -> Tree {
+        match (self.left(), self.right()) {
+            (Some(left), Some(right)) => {
+                Tree::Pair(Box::new(left.to_tree()), Box::new(right.to_tree()))
+            }
+            _ => Tree::Leaf(self.borrow().data.unwrap()),
+        }
+    }
+
+    /// Leaves hold a value and no children, pairs hold two children and no value
+    fn is_valid(&self) -> bool {
+        match (self.left(), self.right()) {
+            (Some(left), Some(right)) => {
+                self.borrow().data.is_none() && left.is_valid() && right.is_valid()
+            }
+            (None, None) => self.is_leaf(),
+            _ => false,
+        }
     }
 
     fn insert_left(&mut self, data: Option<u64>) {
@@ -224,36 +268,65 @@ impl Node for SnailNumber {
     }
 }
 
-#[aoc_generator(day18, part1)]
-pub fn input_generator(input: &str) -> Vec<SnailNumber> {
-    input.lines().fold(Vec::new(), |mut acc, el| {
-        acc.push(SnailNumber::from_str(el));
-        acc
-    })
+/// Leaves are stored as their value and pairs as a marker followed by both halves, which is the
+/// same tree as the text without its punctuation
+impl Snapshot for Tree {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Tree::Leaf(val) => (0_u8, *val).encode(out),
+            Tree::Pair(left, right) => {
+                1_u8.encode(out);
+                left.encode(out);
+                right.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(Tree::Leaf(u64::decode(input)?)),
+            1 => {
+                let (left, right) = (Self::decode(input)?, Self::decode(input)?);
+                Some(Tree::Pair(Box::new(left), Box::new(right)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[aoc_generator(day18)]
+pub fn input_generator(input: &str) -> Result<Vec<Tree>, ParseError> {
+    let numbers = lines(18, input)
+        .map(|line| Ok(SnailNumber::from_line(&line)?.to_tree()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.is_empty() {
+        return Err(ParseError::eof(18, input, "no snailfish numbers"));
+    }
+    Ok(numbers)
 }
 
+// Adding and reducing mutates the linked nodes in place, so the solvers build fresh ones from the
+// parsed trees every time they need a number
 #[aoc(day18, part1, day18_1)]
-pub fn part1(input: &[SnailNumber]) -> u64 {
-    let mut start_node = input[0].clone();
-    for i in 1..input.len() {
-        let other = input[i].clone();
-        start_node = start_node.add(other);
+pub fn part1(input: &[Tree]) -> u64 {
+    let mut start_node = SnailNumber::from_tree(&input[0]);
+    for other in &input[1..] {
+        start_node = start_node.add(SnailNumber::from_tree(other));
         while start_node.reduce() {}
     }
     start_node.magnitude()
 }
 
 #[aoc(day18, part2, day18_2)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(numbers: &[Tree]) -> u64 {
     let mut max = 0;
-    let numbers: Vec<_> = input.lines().collect();
     for i in 0..numbers.len() {
         for j in 0..numbers.len() {
             if i == j {
                 continue;
             }
-            let i_num = SnailNumber::from_str(numbers[i]);
-            let j_num = SnailNumber::from_str(numbers[j]);
+            let i_num = SnailNumber::from_tree(&numbers[i]);
+            let j_num = SnailNumber::from_tree(&numbers[j]);
             let mut node = i_num.add(j_num);
             while node.reduce() {}
             max = max.max(node.magnitude());
@@ -278,12 +351,36 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part1(&input), 4140)
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input), 4140);
+        // the input must survive being solved
+        assert_eq!(part1(&input), 4140);
+        let first = SnailNumber::from_tree(&input[0]);
+        assert_eq!(first.to_string(), SAMPLE.lines().next().unwrap());
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part2(&SAMPLE), 3993)
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), 3993)
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let leaf = |val| Box::new(Tree::Leaf(val));
+        let tree = Tree::Pair(leaf(255), Box::new(Tree::Pair(leaf(0), leaf(u64::MAX))));
+        assert_eq!(Tree::from_bytes(&tree.to_bytes()), Some(tree));
+        assert_eq!(Tree::from_bytes(&[2, 1]), None);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let err = input_generator("[1,2]\n[[1,2],3").unwrap_err();
+        assert_eq!(
+            (err.line, err.reason.as_str()),
+            (2, "incomplete snailfish number")
+        );
+        let err = input_generator("[1,x]").unwrap_err();
+        assert_eq!(err.column, 4);
     }
 }
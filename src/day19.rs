@@ -0,0 +1,348 @@
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
+use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub type Point = [i64; 3];
+
+/// Minimum number of shared beacons for two scanners to be considered overlapping
+const MIN_OVERLAP: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    beacons: Vec<Point>,
+}
+
+impl Snapshot for Scanner {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.beacons.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let beacons = Vec::decode(input)?;
+        Some(Self { beacons })
+    }
+}
+
+fn parse_point(line: &Line) -> Result<Point, ParseError> {
+    let mut coord = line.text.split(',');
+    Ok([
+        line.parse(line.next(&mut coord, "x coordinate")?)?,
+        line.parse(line.next(&mut coord, "y coordinate")?)?,
+        line.parse(line.next(&mut coord, "z coordinate")?)?,
+    ])
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rotation {
+    axes: [usize; 3],
+    signs: [i64; 3],
+}
+
+impl Rotation {
+    /// All 24 proper rotations, i.e. the axis permutations and sign flips with a determinant of 1
+    fn all() -> Vec<Self> {
+        const PERMUTATIONS: [([usize; 3], i64); 6] = [
+            ([0, 1, 2], 1),
+            ([1, 2, 0], 1),
+            ([2, 0, 1], 1),
+            ([0, 2, 1], -1),
+            ([2, 1, 0], -1),
+            ([1, 0, 2], -1),
+        ];
+        let mut out = Vec::with_capacity(24);
+        for (axes, parity) in PERMUTATIONS {
+            for mask in 0..8 {
+                let signs = [0, 1, 2].map(|bit| if mask & (1 << bit) == 0 { 1 } else { -1 });
+                if signs.iter().product::<i64>() * parity == 1 {
+                    out.push(Self { axes, signs });
+                }
+            }
+        }
+        out
+    }
+
+    fn apply(&self, point: &Point) -> Point {
+        [0, 1, 2].map(|i| point[self.axes[i]] * self.signs[i])
+    }
+}
+
+fn sub(a: &Point, b: &Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn add(a: &Point, b: &Point) -> Point {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn manhattan(a: &Point, b: &Point) -> i64 {
+    sub(a, b).iter().map(|n| n.abs()).sum()
+}
+
+/// Tries every rotation of `beacons` against an already aligned set, returning the beacons
+/// in the aligned frame along with the scanner position when enough of them overlap
+fn align(
+    aligned: &[Point],
+    beacons: &[Point],
+    rotations: &[Rotation],
+) -> Option<(Vec<Point>, Point)> {
+    for rotation in rotations {
+        let rotated: Vec<_> = beacons.iter().map(|b| rotation.apply(b)).collect();
+        let mut offsets: HashMap<Point, usize> = HashMap::new();
+        for a in aligned {
+            for b in &rotated {
+                let offset = sub(a, b);
+                let count = offsets.entry(offset).or_default();
+                *count += 1;
+                if *count >= MIN_OVERLAP {
+                    let translated = rotated.iter().map(|b| add(b, &offset)).collect();
+                    return Some((translated, offset));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Aligns every scanner to the frame of scanner 0 and returns the set of unique beacons along
+/// with the position of each scanner
+fn locate(scanners: &[Scanner]) -> Result<(HashSet<Point>, Vec<Point>), String> {
+    let rotations = Rotation::all();
+    let mut positions: Vec<Option<Point>> = vec![None; scanners.len()];
+    let mut aligned: Vec<Option<Vec<Point>>> = vec![None; scanners.len()];
+    positions[0] = Some([0, 0, 0]);
+    aligned[0] = Some(scanners[0].beacons.clone());
+
+    let mut queue = VecDeque::from([0]);
+    while let Some(reference) = queue.pop_front() {
+        let known = aligned[reference].clone().unwrap();
+        for i in 0..scanners.len() {
+            if aligned[i].is_some() {
+                continue;
+            }
+            if let Some((beacons, position)) = align(&known, &scanners[i].beacons, &rotations) {
+                aligned[i] = Some(beacons);
+                positions[i] = Some(position);
+                queue.push_back(i);
+            }
+        }
+    }
+
+    if aligned.iter().any(|a| a.is_none()) {
+        return Err("some scanners could not be aligned".to_string());
+    }
+    let beacons = aligned.into_iter().flatten().flatten().collect();
+    let positions = positions.into_iter().flatten().collect();
+    Ok((beacons, positions))
+}
+
+#[aoc_generator(day19)]
+pub fn input_generator(input: &str) -> Result<Vec<Scanner>, ParseError> {
+    let mut scanners: Vec<Scanner> = Vec::new();
+    for line in lines(19, input) {
+        if line.text.trim().is_empty() {
+            continue;
+        }
+        if line.text.starts_with("---") {
+            scanners.push(Scanner {
+                beacons: Vec::new(),
+            });
+            continue;
+        }
+        let scanner = scanners
+            .last_mut()
+            .ok_or_else(|| line.error("expected a `--- scanner N ---` header"))?;
+        scanner.beacons.push(parse_point(&line)?);
+    }
+    if scanners.is_empty() {
+        return Err(ParseError::eof(19, input, "no scanners"));
+    }
+    Ok(scanners)
+}
+
+#[aoc(day19, part1, day19_1)]
+pub fn part1(input: &[Scanner]) -> Result<usize, String> {
+    let (beacons, _) = locate(input)?;
+    Ok(beacons.len())
+}
+
+#[aoc(day19, part2, day19_2)]
+pub fn part2(input: &[Scanner]) -> Result<i64, String> {
+    let (_, positions) = locate(input)?;
+    let mut max = 0;
+    for a in &positions {
+        for b in &positions {
+            max = max.max(manhattan(a, b));
+        }
+    }
+    Ok(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const SAMPLE: &str = "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
+    #[test]
+    fn test_part_1() {
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input), Ok(79));
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), Ok(3621));
+    }
+
+    #[test]
+    fn test_scanner_positions() {
+        let input = input_generator(SAMPLE).unwrap();
+        let (_, positions) = locate(&input).unwrap();
+        assert_eq!(
+            positions,
+            vec![
+                [0, 0, 0],
+                [68, -1246, -43],
+                [1105, -1205, 1229],
+                [-92, -2380, -20],
+                [-20, -1133, 1061]
+            ]
+        );
+    }
+}
@@ -1,51 +1,277 @@
+use crate::export::Image;
+use crate::grid::Grid;
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::fmt;
+use std::iter::Enumerate;
+use std::slice;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
+}
+
+impl Command {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
+        let (action, count) = line.split_once(line.text, " ")?;
+        let command = match action {
+            "forward" => Command::Forward,
+            "down" => Command::Down,
+            "up" => Command::Up,
+            _ => return Err(line.error_at(action, format!("unknown command `{}`", action))),
+        };
+        Ok(command(line.parse(count)?))
+    }
+}
+
+impl Snapshot for Command {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Command::Forward(count) => (0_u8, count).encode(out),
+            Command::Down(count) => (1_u8, count).encode(out),
+            Command::Up(count) => (2_u8, count).encode(out),
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match <(u8, i64)>::decode(input)? {
+            (0, count) => Some(Command::Forward(count)),
+            (1, count) => Some(Command::Down(count)),
+            (2, count) => Some(Command::Up(count)),
+            _ => None,
+        }
+    }
+}
 
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<(String, i64)> {
-    input
-        .lines()
-        .map(|n| {
-            let mut parts = n.split(" ");
-            (
-                parts.next().unwrap().to_string(),
-                parts.next().unwrap().parse().unwrap(),
-            )
+pub fn input_generator(input: &str) -> Result<Vec<Command>, ParseError> {
+    lines(2, input).map(|l| Command::from_line(&l)).collect()
+}
+
+/// Where the submarine is. `aim` only matters to the models that use it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Submarine {
+    pub horizontal: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+/// How a submarine moves on a command, `None` when it would leave the range of `i64`
+pub trait Movement {
+    fn apply(&self, submarine: Submarine, command: Command) -> Option<Submarine>;
+}
+
+/// `down` and `up` change the depth directly, as in part 1
+pub struct Direct;
+
+impl Movement for Direct {
+    fn apply(&self, submarine: Submarine, command: Command) -> Option<Submarine> {
+        let Submarine {
+            horizontal, depth, ..
+        } = submarine;
+        Some(match command {
+            Command::Forward(val) => Submarine {
+                horizontal: horizontal.checked_add(val)?,
+                ..submarine
+            },
+            Command::Down(val) => Submarine {
+                depth: depth.checked_add(val)?,
+                ..submarine
+            },
+            Command::Up(val) => Submarine {
+                depth: depth.checked_sub(val)?,
+                ..submarine
+            },
         })
-        .collect()
+    }
 }
 
-#[aoc(day2, part1, d21)]
-pub fn part1(commands: &[(String, i64)]) -> i64 {
-    let mut depth = 0;
-    let mut horizontal = 0;
-    for (action, count) in commands {
-        match (action.as_str(), count) {
-            ("forward", val) => horizontal += val,
-            ("up", val) => depth -= val,
-            ("down", val) => depth += val,
-            _ => unreachable!(),
+/// `down` and `up` turn the submarine and `forward` dives along its aim, as in part 2
+pub struct Aim;
+
+impl Movement for Aim {
+    fn apply(&self, submarine: Submarine, command: Command) -> Option<Submarine> {
+        let Submarine {
+            horizontal,
+            depth,
+            aim,
+        } = submarine;
+        Some(match command {
+            Command::Forward(val) => Submarine {
+                horizontal: horizontal.checked_add(val)?,
+                depth: depth.checked_add(aim.checked_mul(val)?)?,
+                aim,
+            },
+            Command::Down(val) => Submarine {
+                aim: aim.checked_add(val)?,
+                ..submarine
+            },
+            Command::Up(val) => Submarine {
+                aim: aim.checked_sub(val)?,
+                ..submarine
+            },
+        })
+    }
+}
+
+/// Where following the commands goes wrong. Commands are counted from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Incident {
+    /// The submarine went above the surface and ended up there
+    Surfaced { index: usize, submarine: Submarine },
+    /// A coordinate of the submarine left the range of `i64`
+    Overflow { index: usize, command: Command },
+    /// The route was fine but horizontal position times depth is out of the range of `i64`
+    Position { submarine: Submarine },
+}
+
+impl fmt::Display for Incident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Incident::Surfaced { index, submarine } => write!(
+                f,
+                "command {} surfaces the submarine at depth {}",
+                index + 1,
+                submarine.depth
+            ),
+            Incident::Overflow { index, command } => {
+                write!(f, "command {} `{}` overflows", index + 1, command)
+            }
+            Incident::Position { submarine } => write!(
+                f,
+                "position {} times depth {} overflows",
+                submarine.horizontal, submarine.depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Incident {}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Forward(val) => write!(f, "forward {}", val),
+            Command::Down(val) => write!(f, "down {}", val),
+            Command::Up(val) => write!(f, "up {}", val),
+        }
+    }
+}
+
+/// Steps through the commands, yielding the submarine after each of them up to the first incident
+pub struct Replay<'a, M> {
+    model: &'a M,
+    commands: Enumerate<slice::Iter<'a, Command>>,
+    /// `None` once an incident ended the replay
+    submarine: Option<Submarine>,
+}
+
+impl<M: Movement> Iterator for Replay<'_, M> {
+    type Item = Result<Submarine, Incident>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let submarine = self.submarine?;
+        let (index, &command) = self.commands.next()?;
+        let step = match self.model.apply(submarine, command) {
+            None => Err(Incident::Overflow { index, command }),
+            Some(next) if next.depth < 0 => Err(Incident::Surfaced {
+                index,
+                submarine: next,
+            }),
+            Some(next) => Ok(next),
+        };
+        self.submarine = step.ok();
+        Some(step)
+    }
+}
+
+impl Submarine {
+    pub fn replay<'a, M: Movement>(self, model: &'a M, commands: &'a [Command]) -> Replay<'a, M> {
+        Replay {
+            model,
+            commands: commands.iter().enumerate(),
+            submarine: Some(self),
         }
     }
-    depth * horizontal
+
+    /// Where the commands take the submarine, or the first incident on the way
+    pub fn run<M: Movement>(self, model: &M, commands: &[Command]) -> Result<Self, Incident> {
+        self.replay(model, commands).try_fold(self, |_, step| step)
+    }
+
+    /// The submarine after every command, starting with where it was before the first one and
+    /// stopping before the first incident
+    pub fn trajectory<M: Movement>(self, model: &M, commands: &[Command]) -> Vec<Self> {
+        let mut positions = vec![self];
+        positions.extend(self.replay(model, commands).map_while(Result::ok));
+        positions
+    }
+}
+
+/// Horizontal position times depth at the end of the route, which has to stay below the surface
+fn position<M: Movement>(model: &M, commands: &[Command]) -> Result<i64, Incident> {
+    let submarine = Submarine::default().run(model, commands)?;
+    submarine
+        .depth
+        .checked_mul(submarine.horizontal)
+        .ok_or(Incident::Position { submarine })
+}
+
+#[aoc(day2, part1, d21)]
+pub fn part1(commands: &[Command]) -> Result<i64, Incident> {
+    position(&Direct, commands)
 }
 
 #[aoc(day2, part2, d22)]
-pub fn part2(commands: &[(String, i64)]) -> i64 {
-    let mut depth = 0;
-    let mut horizontal = 0;
-    let mut aim = 0;
-    for (action, count) in commands {
-        match (action.as_str(), count) {
-            ("forward", val) => {
-                horizontal += val;
-                depth += aim * val
-            }
-            ("up", val) => aim -= val,
-            ("down", val) => aim += val,
-            _ => unreachable!(),
+pub fn part2(commands: &[Command]) -> Result<i64, Incident> {
+    position(&Aim, commands)
+}
+
+/// Size of the route pictures, routes get stretched to fill them
+const PLOT_WIDTH: usize = 200;
+const PLOT_HEIGHT: usize = 100;
+
+/// Draws a route from left to right with the depth going down the picture
+fn plot(route: &[Submarine]) -> Image {
+    let bounds = |coord: fn(&Submarine) -> i64| {
+        let (min, max) = route
+            .iter()
+            .map(coord)
+            .fold((i64::MAX, i64::MIN), |(lo, hi), c| (lo.min(c), hi.max(c)));
+        move |sub: &Submarine, size: usize| {
+            // the span of a route can be wider than an `i64`
+            let offset = (coord(sub) as i128 - min as i128) * (size as i128 - 1);
+            (offset / (max as i128 - min as i128).max(1)) as i64
+        }
+    };
+    let (col, row) = (bounds(|s| s.horizontal), bounds(|s| s.depth));
+    let mut grid = Grid::filled(PLOT_WIDTH, PLOT_HEIGHT, false);
+    let cells: Vec<_> = route
+        .iter()
+        .map(|sub| (row(sub, PLOT_HEIGHT), col(sub, PLOT_WIDTH)))
+        .collect();
+    grid.set((cells[0].0 as usize, cells[0].1 as usize), true);
+    for pair in cells.windows(2) {
+        let ((r1, c1), (r2, c2)) = (pair[0], pair[1]);
+        let steps = (r2 - r1).abs().max((c2 - c1).abs()).max(1);
+        for i in 0..=steps {
+            let pos = (r1 + (r2 - r1) * i / steps, c1 + (c2 - c1) * i / steps);
+            grid.set((pos.0 as usize, pos.1 as usize), true);
         }
     }
-    depth * horizontal
+    Image::bitmap(&grid, |&lit| lit)
+}
+
+/// Routes of the submarine under both movement models
+pub(crate) fn images(commands: &[Command]) -> Vec<(&'static str, Image)> {
+    let start = Submarine::default();
+    vec![
+        ("route-direct", plot(&start.trajectory(&Direct, commands))),
+        ("route-aim", plot(&start.trajectory(&Aim, commands))),
+    ]
 }
 
 #[cfg(test)]
@@ -55,13 +281,148 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part1(&input), 150);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input), Ok(150));
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part2(&input), 900);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), Ok(900));
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let input = input_generator(SAMPLE).unwrap();
+        let route: Vec<_> = Submarine::default()
+            .trajectory(&Aim, &input)
+            .iter()
+            .map(|s| (s.horizontal, s.depth))
+            .collect();
+        assert_eq!(
+            route,
+            [
+                (0, 0),
+                (5, 0),
+                (5, 0),
+                (13, 40),
+                (13, 40),
+                (13, 40),
+                (15, 60)
+            ]
+        );
+        let direct = Submarine::default().trajectory(&Direct, &input);
+        assert_eq!(direct.len(), input.len() + 1);
+        assert_eq!(
+            direct.last().copied(),
+            Submarine::default().run(&Direct, &input).ok()
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let start = Submarine::default();
+        let input = input_generator("down 2\nforward 1\nup 3\ndown 5").unwrap();
+        let steps: Vec<_> = start.replay(&Direct, &input).collect();
+        let surfaced = Submarine {
+            horizontal: 1,
+            depth: -1,
+            aim: 0,
+        };
+        assert_eq!(steps.len(), 3);
+        assert_eq!(
+            steps[2],
+            Err(Incident::Surfaced {
+                index: 2,
+                submarine: surfaced
+            })
+        );
+        assert_eq!(start.trajectory(&Direct, &input).len(), 3);
+        // the same commands only turn the submarine under the aim model
+        assert!(start.run(&Aim, &input).is_ok());
+
+        let input = input_generator("up 1\nforward 4").unwrap();
+        let err = start.run(&Aim, &input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "command 2 surfaces the submarine at depth -4"
+        );
+
+        let input = input_generator("down 9223372036854775807\nforward 2").unwrap();
+        assert_eq!(
+            start.run(&Aim, &input),
+            Err(Incident::Overflow {
+                index: 1,
+                command: Command::Forward(2)
+            })
+        );
+        assert!(start.run(&Direct, &input).is_ok());
+        let err = start.run(
+            &Direct,
+            &[Command::Forward(-i64::MAX), Command::Forward(-2)],
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "command 2 `forward -2` overflows"
+        );
+    }
+
+    #[test]
+    fn test_parts() {
+        let surfaced = Submarine {
+            depth: -3,
+            ..Submarine::default()
+        };
+        assert_eq!(
+            part1(&[Command::Up(3)]),
+            Err(Incident::Surfaced {
+                index: 0,
+                submarine: surfaced
+            })
+        );
+        let commands = [Command::Forward(i64::MAX), Command::Down(2)];
+        let err = part1(&commands).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("position {} times depth 2 overflows", i64::MAX)
+        );
+        assert_eq!(part2(&commands), Ok(0));
+    }
+
+    #[test]
+    fn test_images() {
+        let input = input_generator(SAMPLE).unwrap();
+        let named = images(&input);
+        let Image::Bitmap(aim) = &named[1].1 else {
+            panic!("routes are black and white");
+        };
+        assert_eq!(
+            (named[1].0, aim.width(), aim.height()),
+            ("route-aim", 200, 100)
+        );
+        // the route starts at the surface and ends as far and as deep as it ever goes
+        assert!(aim[(0, 0)] && aim[(99, 199)]);
+        assert!(!aim[(99, 0)]);
+        let Image::Bitmap(still) = plot(&[Submarine::default()]) else {
+            unreachable!();
+        };
+        assert_eq!(still.iter().filter(|&&lit| lit).count(), 1);
+
+        // every position fits in an `i64`, the distance between them doesn't
+        let input =
+            input_generator(&format!("forward -{0}\nforward {0}\nforward {0}", i64::MAX)).unwrap();
+        assert!(Submarine::default().run(&Direct, &input).is_ok());
+        let Image::Bitmap(direct) = &images(&input)[0].1 else {
+            unreachable!();
+        };
+        assert!(direct[(0, 0)] && direct[(0, 199)]);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(input_generator("up 3").unwrap(), [Command::Up(3)]);
+        let err = input_generator("forward 5\nbackward x").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(input_generator("down x").is_err());
     }
 }
@@ -1,8 +1,14 @@
+use crate::export;
+use crate::grid::Grid;
+use crate::parse::{lines, ParseError};
+use crate::simulation::Simulation;
+use crate::snapshot::Snapshot;
+use crate::viz::{self, Frames};
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[derive(Debug, Clone)]
 pub struct Image {
-    img: Vec<Vec<bool>>,
+    img: Grid<bool>,
     lookup: [bool; 512],
     world_alight: bool,
 }
@@ -10,19 +16,14 @@ pub struct Image {
 impl Image {
     fn new() -> Self {
         Self {
-            img: Vec::new(),
+            img: Grid::filled(0, 0, false),
             lookup: [false; 512],
             world_alight: false,
         }
     }
 
     fn get(&self, row: i64, col: i64) -> bool {
-        if row < 0 || col < 0 || row as usize >= self.img.len() || col as usize >= self.img[0].len()
-        {
-            return self.world_alight;
-        }
-        let (row, col) = (row as usize, col as usize);
-        self.img[row][col]
+        *self.img.get_signed(row, col).unwrap_or(&self.world_alight)
     }
 
     fn enhance_pixel(&self, window: [bool; 9]) -> bool {
@@ -36,85 +37,133 @@ impl Image {
         self.lookup[idx]
     }
 
-    fn enhance(&mut self) {
-        let (m, n) = (self.img.len() as i64, self.img[0].len() as i64);
-        let mut new_image = Vec::new();
+    fn count_lit(&self) -> u64 {
+        self.img.iter().filter(|&&lit| lit).count() as u64
+    }
+}
+
+impl Snapshot for Image {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.img.encode(out);
+        self.lookup.encode(out);
+        self.world_alight.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let img = Snapshot::decode(input)?;
+        let lookup = Snapshot::decode(input)?;
+        let world_alight = Snapshot::decode(input)?;
+        Some(Self {
+            img,
+            lookup,
+            world_alight,
+        })
+    }
+}
+
+/// Every step enhances the image once
+impl Simulation for Image {
+    fn step(&mut self) {
+        let (m, n) = (self.img.height(), self.img.width());
         let mut window = [false; 9];
 
-        for row in -2..m {
-            let mut new_row = Vec::new();
-            for col in -2..n {
-                for r in 0..3 {
-                    for c in 0..3 {
-                        let idx = (r * 3 + c) as usize;
-                        window[idx] = self.get(row + r, col + c);
-                    }
+        let new_image = Grid::from_fn(n + 2, m + 2, |(row, col)| {
+            // the new image grows by one pixel on every side
+            let (row, col) = (row as i64 - 2, col as i64 - 2);
+            for r in 0..3 {
+                for c in 0..3 {
+                    let idx = (r * 3 + c) as usize;
+                    window[idx] = self.get(row + r, col + c);
                 }
-                new_row.push(self.enhance_pixel(window));
             }
-            new_image.push(new_row);
-        }
+            self.enhance_pixel(window)
+        });
         self.img = new_image;
-        if self.lookup[0] && !self.lookup[511] {
-            self.world_alight = !self.world_alight;
-        }
+        // the infinite background is a window of nine identical pixels
+        self.world_alight = self.lookup[if self.world_alight { 511 } else { 0 }];
     }
+}
 
-    fn run_enhancement(&mut self, rounds: usize) {
-        for _ in 0..rounds {
-            self.enhance();
+/// The image through the fifty enhancements of part 2
+pub(crate) fn frames(image: Image) -> Frames {
+    let mut image = Some(image);
+    let mut round = 0;
+    Box::new(std::iter::from_fn(move || {
+        let current = image.take()?;
+        let background = if current.world_alight { "lit" } else { "dark" };
+        let frame = format!(
+            "enhancement {}, {} lit, background {}\n{}",
+            round,
+            current.count_lit(),
+            background,
+            viz::picture(current.img.width(), current.img.height(), |pos| current.img
+                [pos])
+        );
+        if round < 50 {
+            let mut next = current;
+            next.step();
+            image = Some(next);
         }
-    }
-
-    fn count_lit(&self) -> u64 {
-        self.img
-            .iter()
-            .map(|n| n.iter().filter(|&n| *n).count())
-            .sum::<usize>() as u64
-    }
+        round += 1;
+        Some(frame)
+    }))
+}
 
-    #[allow(dead_code)]
-    fn print(&self) {
-        let mut out = String::new();
-        for row in 0..self.img.len() {
-            for col in 0..self.img[0].len() {
-                out.push(if self.img[row][col] { '#' } else { '.' });
-            }
-            out.push('\n');
-        }
-        println!("{}", out);
+/// The image after the enhancements of both parts
+pub(crate) fn images(image: &Image) -> Vec<(&'static str, export::Image)> {
+    let mut image = image.clone();
+    let mut out = Vec::new();
+    for (name, rounds) in [("enhanced-2", 2), ("enhanced-50", 48)] {
+        image.run(rounds);
+        out.push((name, export::Image::bitmap(&image.img, |&lit| lit)));
     }
+    out
 }
 
 #[aoc_generator(day20)]
-pub fn input_generator(input: &str) -> Image {
+pub fn input_generator(input: &str) -> Result<Image, ParseError> {
     let mut image = Image::new();
-    let mut iter = input.lines();
-    for (i, c) in iter.next().unwrap().chars().enumerate() {
-        if c == '#' {
-            image.lookup[i] = true;
+    let mut iter = lines(20, input);
+    let lookup = iter
+        .next()
+        .ok_or_else(|| ParseError::eof(20, input, "missing enhancement algorithm"))?;
+    if lookup.text.len() != image.lookup.len() {
+        let reason = format!(
+            "enhancement algorithm needs {} pixels, found {}",
+            image.lookup.len(),
+            lookup.text.len()
+        );
+        return Err(lookup.error(reason));
+    }
+    for (i, c) in lookup.text.char_indices() {
+        match c {
+            '#' => image.lookup[i] = true,
+            '.' => {}
+            _ => return Err(lookup.error_at(&lookup.text[i..], "expected `#` or `.`")),
         }
     }
     iter.next();
-    while let Some(line) = iter.next() {
-        let row: Vec<_> = line.chars().map(|n| n == '#').collect();
-        image.img.push(row);
+    image.img = Grid::from_map(iter)?;
+    if image.img.is_empty() {
+        return Err(ParseError::eof(20, input, "missing input image"));
     }
-    image
+    Ok(image)
 }
 
-#[aoc(day20, part1, day20_1)]
-pub fn part1(input: &Image) -> u64 {
+pub fn lit_after(input: &Image, rounds: usize) -> u64 {
     let mut image = input.clone();
-    image.run_enhancement(2);
+    image.run(rounds);
     image.count_lit()
 }
 
+#[aoc(day20, part1, day20_1)]
+pub fn part1(input: &Image) -> u64 {
+    lit_after(input, 2)
+}
+
 #[aoc(day20, part2, day20_2)]
 pub fn part2(input: &Image) -> u64 {
-    let mut image = input.clone();
-    image.run_enhancement(50);
-    image.count_lit()
+    lit_after(input, 50)
 }
 
 #[cfg(test)]
@@ -137,13 +186,14 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(lit_after(&input, 0), 10);
         assert_eq!(part1(&input), 35)
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 3351)
     }
 }
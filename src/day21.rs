@@ -1,3 +1,4 @@
+use crate::parse::{lines, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::HashMap;
 
@@ -19,27 +20,28 @@ impl Dice {
     }
 
     fn get(&mut self, num: usize) -> u64 {
-        let t = (0..num).fold(0, |mut acc, _| {
+        (0..num).fold(0, |mut acc, _| {
             acc += self.next();
             acc
-        });
-        t
+        })
     }
 }
 
 #[aoc_generator(day21)]
-pub fn input_generator(input: &str) -> (u64, u64) {
-    let mut iter = input.lines();
-    let mut next_digit = || {
-        iter.next()
-            .unwrap()
-            .chars()
-            .last()
-            .unwrap()
-            .to_digit(10)
-            .unwrap() as u64
+pub fn input_generator(input: &str) -> Result<(u64, u64), ParseError> {
+    let mut iter = lines(21, input);
+    let mut next_position = |player: u64| {
+        let line = iter.next().ok_or_else(|| {
+            ParseError::eof(21, input, format!("missing player {} position", player))
+        })?;
+        let (_, field) = line.split_once(line.text, "starting position: ")?;
+        let position: u64 = line.parse(field)?;
+        if !(1..=10).contains(&position) {
+            return Err(line.error_at(field, "position must be between 1 and 10"));
+        }
+        Ok(position)
     };
-    (next_digit(), next_digit())
+    Ok((next_position(1)?, next_position(2)?))
 }
 
 #[aoc(day21, part1, day21_1)]
@@ -48,7 +50,7 @@ pub fn part1(input: &(u64, u64)) -> u64 {
     let (mut p_one_score, mut p_two_score) = (0, 0);
     let mut dice = Dice::new();
     loop {
-        p_one_pos = p_one_pos + dice.get(3);
+        p_one_pos += dice.get(3);
         p_one_pos = if p_one_pos % 10 != 0 {
             p_one_pos % 10
         } else {
@@ -58,7 +60,7 @@ pub fn part1(input: &(u64, u64)) -> u64 {
         if p_one_score >= 1000 {
             return p_two_score * dice.total;
         }
-        p_two_pos = p_two_pos + dice.get(3);
+        p_two_pos += dice.get(3);
         p_two_pos = if p_two_pos % 10 != 0 {
             p_two_pos % 10
         } else {
@@ -73,11 +75,14 @@ pub fn part1(input: &(u64, u64)) -> u64 {
 
 const FREQ: [(u64, u64); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
 
+/// Universes in which the player about to move and the other player win, the first one to reach
+/// `target` winning the game
 fn recurse(
     pos: u64,
     other_pos: u64,
     score: u64,
     other_score: u64,
+    target: u64,
     cache: &mut HashMap<(u64, u64, u64, u64), (u64, u64)>,
 ) -> (u64, u64) {
     let key = (pos, other_pos, score, other_score);
@@ -85,20 +90,27 @@ fn recurse(
         return *val;
     }
 
-    if other_score >= 21 {
+    if other_score >= target {
         return (0, 1);
     }
 
     let mut ways = (0, 0);
     for (roll, freq) in FREQ {
-        let new_position = if (pos + roll) % 10 != 0 {
+        let new_position = if !(pos + roll).is_multiple_of(10) {
             (pos + roll) % 10
         } else {
             10
         };
         let new_score = score + new_position;
 
-        let outcome = recurse(other_pos, new_position, other_score, new_score, cache);
+        let outcome = recurse(
+            other_pos,
+            new_position,
+            other_score,
+            new_score,
+            target,
+            cache,
+        );
 
         ways.0 += freq * outcome.1;
         ways.1 += freq * outcome.0;
@@ -110,25 +122,61 @@ fn recurse(
 
 #[aoc(day21, part2, day21_e)]
 pub fn part2(input: &(u64, u64)) -> u64 {
-    let (score_1, score_2) = recurse(input.0, input.1, 0, 0, &mut HashMap::new());
+    let (score_1, score_2) = dirac_wins(input, 21);
     score_1.max(score_2)
 }
 
+/// Universes in which each player wins a game of Dirac Dice played up to `target`
+pub fn dirac_wins(input: &(u64, u64), target: u64) -> (u64, u64) {
+    recurse(input.0, input.1, 0, 0, target, &mut HashMap::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth::Rng;
     const SAMPLE: &str = "Player 1 starting position: 4
 Player 2 starting position: 8";
 
+    /// Walks the whole tree of universes, splitting on every single roll of the die
+    fn brute_force(positions: [u64; 2], scores: [u64; 2], player: usize, target: u64) -> [u64; 2] {
+        let mut wins = [0, 0];
+        for rolls in 0..27 {
+            let roll = 3 + rolls % 3 + rolls / 3 % 3 + rolls / 9;
+            let (mut positions, mut scores) = (positions, scores);
+            positions[player] = (positions[player] + roll - 1) % 10 + 1;
+            scores[player] += positions[player];
+            if scores[player] >= target {
+                wins[player] += 1;
+                continue;
+            }
+            let next = brute_force(positions, scores, 1 - player, target);
+            wins = [wins[0] + next[0], wins[1] + next[1]];
+        }
+        wins
+    }
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 739785);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 444356092776315);
     }
+
+    #[test]
+    fn test_brute_force() {
+        let mut rng = Rng::new(21);
+        for _ in 0..20 {
+            let positions = [rng.range(1..=10) as u64, rng.range(1..=10) as u64];
+            let target = rng.range(1..=9) as u64;
+            let wins = brute_force(positions, [0, 0], 0, target);
+            let fast = dirac_wins(&(positions[0], positions[1]), target);
+            assert_eq!(fast, (wins[0], wins[1]), "{:?} to {}", positions, target);
+        }
+    }
 }
@@ -1,5 +1,7 @@
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum State {
@@ -7,10 +9,12 @@ enum State {
     Off,
 }
 
+type Bounds = ((i64, i64), (i64, i64), (i64, i64));
+
 #[derive(Clone, Debug)]
 pub struct Switch {
     state: State,
-    bounds: ((i64, i64), (i64, i64), (i64, i64)),
+    bounds: Bounds,
 }
 
 impl Switch {
@@ -20,27 +24,27 @@ impl Switch {
             bounds: ((0, 0), (0, 0), (0, 0)),
         }
     }
-    fn from_str(input: &str) -> Self {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
         let mut out = Self::new();
-        let mut iter = input.split(' ');
-        out.state = match iter.next().unwrap() {
+        let (state, cuboid) = line.split_once(line.text, " ")?;
+        out.state = match state {
             "on" => State::On,
             "off" => State::Off,
-            val => panic!("invalid input: {}", val),
+            val => return Err(line.error_at(val, format!("invalid state `{}`", val))),
+        };
+        let mut coord = cuboid.split(',');
+        let mut range = |axis: &str| -> Result<(i64, i64), ParseError> {
+            let field = line.next(&mut coord, &format!("{} range", axis))?;
+            let field = line.strip_prefix(field, &format!("{}=", axis))?;
+            let (start, end) = line.split_once(field, "..")?;
+            Ok((line.parse(start)?, line.parse(end)?))
         };
-        let mut coord = iter.next().unwrap().split(',').map(|n| {
-            let mut parts = n[2..].split("..");
-            (
-                parts.next().unwrap().parse::<i64>().unwrap(),
-                parts.next().unwrap().parse::<i64>().unwrap(),
-            )
-        });
-
-        let (x_start, x_end) = coord.next().unwrap();
-        let (y_start, y_end) = coord.next().unwrap();
-        let (z_start, z_end) = coord.next().unwrap();
+
+        let (x_start, x_end) = range("x")?;
+        let (y_start, y_end) = range("y")?;
+        let (z_start, z_end) = range("z")?;
         out.bounds = ((x_start, x_end), (y_start, y_end), (z_start, z_end));
-        out
+        Ok(out)
     }
 
     fn clip(&mut self, min: i64, max: i64) {
@@ -56,46 +60,116 @@ impl Switch {
         helper(&mut self.bounds.1);
         helper(&mut self.bounds.2);
     }
+}
+
+impl Snapshot for Switch {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.state == State::On).encode(out);
+        self.bounds.encode(out);
+    }
 
-    fn coords(&self) -> Box<dyn Iterator<Item = (i64, i64, i64)>> {
-        let x_range = self.bounds.0 .0..self.bounds.0 .1 + 1;
-        let y_range = self.bounds.1 .0..self.bounds.1 .1 + 1;
-        let z_range = self.bounds.2 .0..self.bounds.2 .1 + 1;
-        Box::new(itertools::iproduct!(x_range, y_range, z_range))
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let state = match bool::decode(input)? {
+            true => State::On,
+            false => State::Off,
+        };
+        let bounds = Bounds::decode(input)?;
+        Some(Self { state, bounds })
     }
 }
 
+fn volume(bounds: &Bounds) -> i64 {
+    let len = |(start, end): (i64, i64)| (end - start + 1).max(0);
+    len(bounds.0) * len(bounds.1) * len(bounds.2)
+}
+
+fn intersect(a: &Bounds, b: &Bounds) -> Option<Bounds> {
+    let helper = |a: (i64, i64), b: (i64, i64)| {
+        let (start, end) = (a.0.max(b.0), a.1.min(b.1));
+        if start <= end {
+            Some((start, end))
+        } else {
+            None
+        }
+    };
+    Some((helper(a.0, b.0)?, helper(a.1, b.1)?, helper(a.2, b.2)?))
+}
+
+/// Computes the lit volume using signed cuboids: every new switch cancels out its overlap with
+/// each cuboid seen so far (by adding the intersection with the opposite sign), and `On`
+/// switches then add their own volume.
+fn lit_volume<'a, I: IntoIterator<Item = &'a Switch>>(switches: I) -> u64 {
+    let mut cuboids: HashMap<Bounds, i64> = HashMap::new();
+    for switch in switches {
+        if volume(&switch.bounds) == 0 {
+            continue;
+        }
+        let mut update: HashMap<Bounds, i64> = HashMap::new();
+        for (bounds, &sign) in &cuboids {
+            if let Some(overlap) = intersect(bounds, &switch.bounds) {
+                *update.entry(overlap).or_default() -= sign;
+            }
+        }
+        if switch.state == State::On {
+            *update.entry(switch.bounds).or_default() += 1;
+        }
+        for (bounds, sign) in update {
+            *cuboids.entry(bounds).or_default() += sign;
+        }
+        cuboids.retain(|_, sign| *sign != 0);
+    }
+    let total: i64 = cuboids
+        .iter()
+        .map(|(bounds, sign)| sign * volume(bounds))
+        .sum();
+    total as u64
+}
+
 #[aoc_generator(day22)]
-pub fn input_generator(input: &str) -> Vec<Switch> {
-    input.lines().fold(Vec::new(), |mut acc, el| {
-        acc.push(Switch::from_str(el));
-        acc
-    })
+pub fn input_generator(input: &str) -> Result<Vec<Switch>, ParseError> {
+    lines(22, input)
+        .map(|line| Switch::from_line(&line))
+        .collect()
 }
 
 #[aoc(day22, part1, day22_1)]
 pub fn part1(input: &[Switch]) -> u64 {
-    let input = input.to_vec();
-    let mut toggled = HashSet::new();
-    for mut switch in input.into_iter() {
+    let clipped: Vec<_> = input
+        .iter()
+        .cloned()
+        .map(|mut switch| {
+            switch.clip(-50, 50);
+            switch
+        })
+        .collect();
+    lit_volume(&clipped)
+}
+
+/// Lights every cube of the initialization area one by one
+#[aoc(day22, part1, day22_1_voxels)]
+pub fn part1_voxels(input: &[Switch]) -> u64 {
+    const SIDE: usize = 101;
+    let mut cubes = vec![false; SIDE * SIDE * SIDE];
+    for switch in input {
+        let mut switch = switch.clone();
         switch.clip(-50, 50);
-        let mut coords = switch.coords();
-        if switch.state == State::Off {
-            while let Some(key) = coords.next() {
-                toggled.remove(&key);
-            }
-        } else {
-            while let Some(key) = coords.next() {
-                toggled.insert(key);
+        let ((x0, x1), (y0, y1), (z0, z1)) = switch.bounds;
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                for z in z0..=z1 {
+                    let idx =
+                        ((x + 50) as usize * SIDE + (y + 50) as usize) * SIDE + (z + 50) as usize;
+                    cubes[idx] = switch.state == State::On;
+                }
             }
         }
     }
-    toggled.len() as u64
+    cubes.iter().filter(|&&on| on).count() as u64
 }
 
-#[aoc(day22, part2, day22_e)]
+#[aoc(day22, part2, day22_2)]
 pub fn part2(input: &[Switch]) -> u64 {
-    0
+    lit_volume(input)
 }
 
 #[cfg(test)]
@@ -124,15 +198,92 @@ on x=-41..9,y=-7..43,z=-33..15
 on x=-54112..-39298,y=-85059..-49293,z=-27449..7877
 on x=967..23432,y=45373..81175,z=27513..53682";
 
+    const SAMPLE_2: &str = "on x=-5..47,y=-31..22,z=-19..33
+on x=-44..5,y=-27..21,z=-14..35
+on x=-49..-1,y=-11..42,z=-10..38
+on x=-20..34,y=-40..6,z=-44..1
+off x=26..39,y=40..50,z=-2..11
+on x=-41..5,y=-41..6,z=-36..8
+off x=-43..-33,y=-45..-28,z=7..25
+on x=-33..15,y=-32..19,z=-34..11
+off x=35..47,y=-46..-34,z=-11..5
+on x=-14..36,y=-6..44,z=-16..29
+on x=-57795..-6158,y=29564..72030,z=20435..90618
+on x=36731..105352,y=-21140..28532,z=16094..90401
+on x=30999..107136,y=-53464..15513,z=8553..71215
+on x=13528..83982,y=-99403..-27377,z=-24141..23996
+on x=-72682..-12347,y=18159..111354,z=7391..80950
+on x=-1060..80757,y=-65301..-20884,z=-103788..-16709
+on x=-83015..-9461,y=-72160..-8347,z=-81239..-26856
+on x=-52752..22273,y=-49450..9096,z=54442..119054
+on x=-29982..40483,y=-108474..-28371,z=-24328..38471
+on x=-4958..62750,y=40422..118853,z=-7672..65583
+on x=55694..108686,y=-43367..46958,z=-26781..48729
+on x=-98497..-18186,y=-63569..3412,z=1232..88485
+on x=-726..56291,y=-62629..13224,z=18033..85226
+on x=-110886..-34664,y=-81338..-8658,z=8914..63723
+on x=-55829..24974,y=-16897..54165,z=-121762..-28058
+on x=-65152..-11147,y=22489..91432,z=-58782..1780
+on x=-120100..-32970,y=-46592..27473,z=-11695..61039
+on x=-18631..37533,y=-124565..-50804,z=-35667..28308
+on x=-57817..18248,y=49321..117703,z=5745..55881
+on x=14781..98692,y=-1341..70827,z=15753..70151
+on x=-34419..55919,y=-19626..40991,z=39015..114138
+on x=-60785..11593,y=-56135..2999,z=-95368..-26915
+on x=-32178..58085,y=17647..101866,z=-91405..-8878
+on x=-53655..12091,y=50097..105568,z=-75335..-4862
+on x=-111166..-40997,y=-71714..2688,z=5609..50954
+on x=-16602..70118,y=-98693..-44401,z=5197..76897
+on x=16383..101554,y=4615..83635,z=-44907..18747
+off x=-95822..-15171,y=-19987..48940,z=10804..104439
+on x=-89813..-14614,y=16069..88491,z=-3297..45228
+on x=41075..99376,y=-20427..49978,z=-52012..13762
+on x=-21330..50085,y=-17944..62733,z=-112280..-30197
+on x=-16478..35915,y=36008..118594,z=-7885..47086
+off x=-98156..-27851,y=-49952..43171,z=-99005..-8456
+off x=2032..69770,y=-71013..4824,z=7471..94418
+on x=43670..120875,y=-42068..12382,z=-24787..38892
+off x=37514..111226,y=-45862..25743,z=-16714..54663
+off x=25699..97951,y=-30668..59918,z=-15349..69697
+off x=-44271..17935,y=-9516..60759,z=49131..112598
+on x=-61695..-5813,y=40978..94975,z=8655..80240
+off x=-101086..-9439,y=-7088..67543,z=33935..83858
+off x=18020..114017,y=-48931..32606,z=21474..89843
+off x=-77139..10506,y=-89994..-18797,z=-80..59318
+off x=8476..79288,y=-75520..11602,z=-96624..-24783
+on x=-47488..-1262,y=24338..100707,z=16292..72967
+off x=-84341..13987,y=2429..92914,z=-90671..-1318
+off x=-37810..49457,y=-71013..-7894,z=-105357..-13188
+off x=-27365..46395,y=31009..98017,z=15428..76570
+off x=-70369..-16548,y=22648..78696,z=-1892..86821
+on x=-53470..21291,y=-120233..-33476,z=-44150..38147
+off x=-93533..-4276,y=-16170..68771,z=-104985..-24507";
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 590784);
+        assert_eq!(part1_voxels(&input), 590784);
+        let input = input_generator(SAMPLE_2).unwrap();
+        assert_eq!(part1(&input), 474140);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part2(&input), 444356092776315);
+        let input = input_generator(SAMPLE_2).unwrap();
+        assert_eq!(part2(&input), 2758514936282235);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let err = input_generator("on x=1..2,y=3..4,z=5..6\ntoggle x=1..2").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = input_generator("on x=1..2,y=3..4").unwrap_err();
+        assert_eq!(err.reason, "missing z range");
+        let err = input_generator("off x=1..2,y=3..a,z=5..6").unwrap_err();
+        assert_eq!(
+            (err.column, err.reason.as_str()),
+            (17, "invalid value `a`: invalid digit found in string")
+        );
     }
 }
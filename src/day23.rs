@@ -0,0 +1,202 @@
+use crate::parse::{lines, ParseError};
+use aoc_runner_derive::{aoc, aoc_generator};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const HALLWAY: usize = 11;
+const ROOMS: usize = 4;
+const DOORS: [usize; ROOMS] = [2, 4, 6, 8];
+const COST: [u64; ROOMS] = [1, 10, 100, 1000];
+const BITS: usize = 3;
+const EMPTY: u8 = 0;
+
+/// The rows of the side rooms, top to bottom, with amphipods `A` to `D` stored as `1` to `4`
+pub type Rooms = Vec<[u8; ROOMS]>;
+
+/// Burrow state packed into a single integer using 3 bits per cell: the 11 hallway cells come
+/// first, followed by each room from top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Burrow(u128);
+
+impl Burrow {
+    fn from_rooms(rooms: &[[u8; ROOMS]]) -> Self {
+        let depth = rooms.len();
+        let mut burrow = Self(0);
+        for (slot, row) in rooms.iter().enumerate() {
+            for (room, &amphipod) in row.iter().enumerate() {
+                burrow = burrow.set(Self::room_cell(depth, room, slot), amphipod);
+            }
+        }
+        burrow
+    }
+
+    fn room_cell(depth: usize, room: usize, slot: usize) -> usize {
+        HALLWAY + room * depth + slot
+    }
+
+    fn get(&self, cell: usize) -> u8 {
+        ((self.0 >> (cell * BITS)) & 0b111) as u8
+    }
+
+    fn set(&self, cell: usize, amphipod: u8) -> Self {
+        let cleared = self.0 & !(0b111 << (cell * BITS));
+        Self(cleared | (amphipod as u128) << (cell * BITS))
+    }
+
+    fn is_organized(&self, depth: usize) -> bool {
+        (0..ROOMS).all(|room| {
+            (0..depth).all(|slot| self.get(Self::room_cell(depth, room, slot)) == room as u8 + 1)
+        })
+    }
+
+    /// A room only accepts amphipods once every amphipod left in it belongs there
+    fn room_accepts(&self, depth: usize, room: usize) -> bool {
+        (0..depth).all(|slot| {
+            let amphipod = self.get(Self::room_cell(depth, room, slot));
+            amphipod == EMPTY || amphipod == room as u8 + 1
+        })
+    }
+
+    fn hallway_clear(&self, from: usize, to: usize) -> bool {
+        let (start, end) = (from.min(to), from.max(to));
+        (start..=end).all(|cell| cell == from || self.get(cell) == EMPTY)
+    }
+
+    fn moves(&self, depth: usize) -> Vec<(Self, u64)> {
+        let mut out = Vec::new();
+
+        // hallway to destination room
+        for cell in 0..HALLWAY {
+            let amphipod = self.get(cell);
+            if amphipod == EMPTY {
+                continue;
+            }
+            let room = (amphipod - 1) as usize;
+            if !self.room_accepts(depth, room) || !self.hallway_clear(cell, DOORS[room]) {
+                continue;
+            }
+            let slot = (0..depth)
+                .rev()
+                .find(|&slot| self.get(Self::room_cell(depth, room, slot)) == EMPTY)
+                .unwrap();
+            let steps = (cell as i64 - DOORS[room] as i64).unsigned_abs() + slot as u64 + 1;
+            let next = self
+                .set(cell, EMPTY)
+                .set(Self::room_cell(depth, room, slot), amphipod);
+            out.push((next, steps * COST[room]));
+        }
+
+        // top of a room to the hallway
+        for (room, &door) in DOORS.iter().enumerate() {
+            if self.room_accepts(depth, room) {
+                continue;
+            }
+            let slot = (0..depth)
+                .find(|&slot| self.get(Self::room_cell(depth, room, slot)) != EMPTY)
+                .unwrap();
+            let from = Self::room_cell(depth, room, slot);
+            let amphipod = self.get(from);
+            for cell in (0..HALLWAY).filter(|cell| !DOORS.contains(cell)) {
+                if self.get(cell) != EMPTY || !self.hallway_clear(door, cell) {
+                    continue;
+                }
+                let steps = (cell as i64 - door as i64).unsigned_abs() + slot as u64 + 1;
+                let next = self.set(from, EMPTY).set(cell, amphipod);
+                out.push((next, steps * COST[(amphipod - 1) as usize]));
+            }
+        }
+        out
+    }
+}
+
+pub(crate) fn organize(rooms: &[[u8; ROOMS]]) -> Option<u64> {
+    let depth = rooms.len();
+    let start = Burrow::from_rooms(rooms);
+    let mut dist: HashMap<Burrow, u64> = HashMap::new();
+    let mut pq = BinaryHeap::new();
+    dist.insert(start, 0);
+    pq.push(Reverse((0, start)));
+
+    while let Some(Reverse((energy, burrow))) = pq.pop() {
+        if burrow.is_organized(depth) {
+            return Some(energy);
+        }
+        if dist.get(&burrow).is_some_and(|&best| best < energy) {
+            continue;
+        }
+        for (next, cost) in burrow.moves(depth) {
+            let next_energy = energy + cost;
+            let best = dist.entry(next).or_insert(u64::MAX);
+            if next_energy < *best {
+                *best = next_energy;
+                pq.push(Reverse((next_energy, next)));
+            }
+        }
+    }
+    None
+}
+
+#[aoc_generator(day23)]
+pub fn input_generator(input: &str) -> Result<Rooms, ParseError> {
+    let mut rooms = Vec::new();
+    for line in lines(23, input).skip(2) {
+        let mut row = Vec::new();
+        for (i, c) in line.text.char_indices() {
+            match c {
+                'A'..='D' => row.push(c as u8 - b'A' + 1),
+                '#' | ' ' => {}
+                _ => return Err(line.error_at(&line.text[i..], "expected an amphipod")),
+            }
+        }
+        match row.len() {
+            0 => continue,
+            ROOMS => rooms.push([row[0], row[1], row[2], row[3]]),
+            len => return Err(line.error(format!("expected {} amphipods, found {}", ROOMS, len))),
+        }
+    }
+    if rooms.is_empty() {
+        return Err(ParseError::eof(23, input, "missing side rooms"));
+    }
+    Ok(rooms)
+}
+
+#[aoc(day23, part1, day23_1)]
+pub fn part1(rooms: &Rooms) -> Result<u64, String> {
+    organize(rooms).ok_or_else(|| "burrow cannot be organized".to_string())
+}
+
+#[aoc(day23, part2, day23_2)]
+pub fn part2(rooms: &Rooms) -> Result<u64, String> {
+    organize(&unfold(rooms)).ok_or_else(|| "burrow cannot be organized".to_string())
+}
+
+/// Inserts the two rows hidden under the diagram
+pub(crate) fn unfold(rooms: &Rooms) -> Rooms {
+    // #D#C#B#A#
+    // #D#B#A#C#
+    let mut unfolded = rooms.clone();
+    unfolded.splice(1..1, [[4, 3, 2, 1], [4, 2, 1, 3]]);
+    unfolded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const SAMPLE: &str = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+
+    #[test]
+    fn test_part_1() {
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input), Ok(12521));
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), Ok(44169));
+    }
+}
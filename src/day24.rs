@@ -0,0 +1,321 @@
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
+use aoc_runner_derive::{aoc, aoc_generator};
+
+const DIGITS: usize = 14;
+const BLOCK_LEN: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Value(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Inp(usize),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+impl Snapshot for Operand {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Operand::Register(r) => (0_u8, r).encode(out),
+            Operand::Value(val) => (1_u8, val).encode(out),
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => usize::decode(input)
+                .filter(|&r| r < 4)
+                .map(Operand::Register),
+            1 => i64::decode(input).map(Operand::Value),
+            _ => None,
+        }
+    }
+}
+
+impl Snapshot for Instruction {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let (op, dest, operand) = match *self {
+            Instruction::Inp(dest) => (0_u8, dest, None),
+            Instruction::Add(dest, operand) => (1, dest, Some(operand)),
+            Instruction::Mul(dest, operand) => (2, dest, Some(operand)),
+            Instruction::Div(dest, operand) => (3, dest, Some(operand)),
+            Instruction::Mod(dest, operand) => (4, dest, Some(operand)),
+            Instruction::Eql(dest, operand) => (5, dest, Some(operand)),
+        };
+        (op, dest as u8).encode(out);
+        if let Some(operand) = operand {
+            operand.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (op, dest) = <(u8, u8)>::decode(input)?;
+        let dest = dest as usize;
+        if dest >= 4 {
+            return None;
+        }
+        if op == 0 {
+            return Some(Instruction::Inp(dest));
+        }
+        let operand = Operand::decode(input)?;
+        Some(match op {
+            1 => Instruction::Add(dest, operand),
+            2 => Instruction::Mul(dest, operand),
+            3 => Instruction::Div(dest, operand),
+            4 => Instruction::Mod(dest, operand),
+            5 => Instruction::Eql(dest, operand),
+            _ => return None,
+        })
+    }
+}
+
+fn register(line: &Line, name: &str) -> Result<usize, ParseError> {
+    match name {
+        "w" => Ok(0),
+        "x" => Ok(1),
+        "y" => Ok(2),
+        "z" => Ok(3),
+        val => Err(line.error_at(val, format!("invalid register `{}`", val))),
+    }
+}
+
+impl Instruction {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
+        let mut parts = line.text.split_whitespace();
+        let op = line.next(&mut parts, "instruction")?;
+        let dest = register(line, line.next(&mut parts, "register")?)?;
+        let mut operand = || match line.next(&mut parts, "operand")? {
+            val @ ("w" | "x" | "y" | "z") => Ok(Operand::Register(register(line, val)?)),
+            val => Ok(Operand::Value(line.parse(val)?)),
+        };
+        Ok(match op {
+            "inp" => Instruction::Inp(dest),
+            "add" => Instruction::Add(dest, operand()?),
+            "mul" => Instruction::Mul(dest, operand()?),
+            "div" => Instruction::Div(dest, operand()?),
+            "mod" => Instruction::Mod(dest, operand()?),
+            "eql" => Instruction::Eql(dest, operand()?),
+            val => return Err(line.error_at(val, format!("invalid instruction `{}`", val))),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Alu {
+    registers: [i64; 4],
+}
+
+impl Alu {
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r],
+            Operand::Value(val) => val,
+        }
+    }
+
+    /// Runs the program to completion, returning `None` if it runs out of input or performs an
+    /// invalid division or modulo
+    fn run(program: &[Instruction], input: &[i64]) -> Option<[i64; 4]> {
+        let mut alu = Self::default();
+        let mut input = input.iter();
+        for &instruction in program {
+            match instruction {
+                Instruction::Inp(r) => alu.registers[r] = *input.next()?,
+                Instruction::Add(r, o) => alu.registers[r] += alu.value(o),
+                Instruction::Mul(r, o) => alu.registers[r] *= alu.value(o),
+                Instruction::Div(r, o) => {
+                    let val = alu.value(o);
+                    if val == 0 {
+                        return None;
+                    }
+                    alu.registers[r] /= val;
+                }
+                Instruction::Mod(r, o) => {
+                    let val = alu.value(o);
+                    if alu.registers[r] < 0 || val <= 0 {
+                        return None;
+                    }
+                    alu.registers[r] %= val;
+                }
+                Instruction::Eql(r, o) => {
+                    alu.registers[r] = (alu.registers[r] == alu.value(o)) as i64
+                }
+            }
+        }
+        Some(alu.registers)
+    }
+}
+
+/// MONAD is made of 14 near identical blocks, one per digit, that differ only by whether they
+/// pop `z` (`div z 26`), the value added to `x` before the digit check and the value added to
+/// `y` before it is pushed onto `z`.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    pops: bool,
+    check: i64,
+    offset: i64,
+}
+
+fn blocks(program: &[Instruction]) -> Result<Vec<Block>, String> {
+    if program.len() != DIGITS * BLOCK_LEN {
+        return Err("program does not look like MONAD".to_string());
+    }
+    program
+        .chunks(BLOCK_LEN)
+        .map(|block| match (block[4], block[5], block[15]) {
+            (
+                Instruction::Div(3, Operand::Value(div)),
+                Instruction::Add(1, Operand::Value(check)),
+                Instruction::Add(2, Operand::Value(offset)),
+            ) => Ok(Block {
+                pops: div == 26,
+                check,
+                offset,
+            }),
+            _ => Err("program does not look like MONAD".to_string()),
+        })
+        .collect()
+}
+
+/// Pairs every pushing block with the block that pops it: the digit of the popping block must
+/// equal the digit of the pushing block plus the pushed offset and the popping check. Each pair
+/// is then pinned to the largest (or smallest) digits satisfying that constraint.
+fn model_number(program: &[Instruction], largest: bool) -> Result<u64, String> {
+    let mut digits = [0_i64; DIGITS];
+    let mut stack = Vec::new();
+    for (i, block) in blocks(program)?.into_iter().enumerate() {
+        if !block.pops {
+            stack.push((i, block.offset));
+            continue;
+        }
+        let (j, offset) = stack.pop().ok_or("unbalanced program")?;
+        let diff = offset + block.check;
+        digits[j] = if largest {
+            9.min(9 - diff)
+        } else {
+            1.max(1 - diff)
+        };
+        digits[i] = digits[j] + diff;
+    }
+    if !stack.is_empty() {
+        return Err("unbalanced program".to_string());
+    }
+    if !digits.iter().all(|d| (1..=9).contains(d)) {
+        return Err("no valid model number".to_string());
+    }
+
+    let registers = Alu::run(program, &digits).ok_or("program failed")?;
+    if registers[3] != 0 {
+        return Err("model number was rejected".to_string());
+    }
+    Ok(digits.iter().fold(0, |acc, &d| acc * 10 + d as u64))
+}
+
+#[aoc_generator(day24)]
+pub fn input_generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    lines(24, input)
+        .filter(|l| !l.text.trim().is_empty())
+        .map(|l| Instruction::from_line(&l))
+        .collect()
+}
+
+#[aoc(day24, part1, day24_1)]
+pub fn part1(program: &[Instruction]) -> Result<u64, String> {
+    model_number(program, true)
+}
+
+#[aoc(day24, part2, day24_2)]
+pub fn part2(program: &[Instruction]) -> Result<u64, String> {
+    model_number(program, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const NEGATE: &str = "inp x
+mul x -1";
+
+    const BINARY: &str = "inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2";
+
+    const PARAMS: [(i64, i64, i64); DIGITS] = [
+        (1, 11, 6),
+        (1, 13, 14),
+        (1, 15, 14),
+        (26, -8, 10),
+        (1, 13, 9),
+        (1, 15, 12),
+        (26, -11, 8),
+        (26, -4, 13),
+        (26, -15, 12),
+        (1, 14, 6),
+        (1, 14, 9),
+        (26, -1, 15),
+        (26, -8, 4),
+        (26, -14, 10),
+    ];
+
+    fn monad() -> String {
+        PARAMS
+            .iter()
+            .map(|(div, check, offset)| {
+                format!(
+                    "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\n\
+                     mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\n\
+                     mul y x\nadd z y",
+                    div, check, offset
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_alu() {
+        let program = input_generator(NEGATE).unwrap();
+        assert_eq!(Alu::run(&program, &[7]), Some([0, -7, 0, 0]));
+        let program = input_generator(BINARY).unwrap();
+        assert_eq!(Alu::run(&program, &[13]), Some([1, 1, 0, 1]));
+        assert_eq!(Alu::run(&program, &[]), None);
+    }
+
+    #[test]
+    fn test_part_1() {
+        let input = input_generator(&monad()).unwrap();
+        assert_eq!(part1(&input), Ok(99394899891971));
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = input_generator(&monad()).unwrap();
+        assert_eq!(part2(&input), Ok(92171126131911));
+    }
+
+    #[test]
+    fn test_not_monad() {
+        let input = input_generator("inp w\nadd z w").unwrap();
+        assert_eq!(
+            part1(&input),
+            Err("program does not look like MONAD".to_string())
+        );
+        let input = input_generator(&monad().replace("div z 26", "div z 1")).unwrap();
+        assert_eq!(part2(&input), Err("unbalanced program".to_string()));
+    }
+}
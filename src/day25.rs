@@ -0,0 +1,95 @@
+use crate::grid::Grid;
+use crate::limits;
+use crate::parse::{lines, ParseError};
+use crate::snapshot::Snapshot;
+use aoc_runner_derive::{aoc, aoc_generator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cucumber {
+    Empty,
+    East,
+    South,
+}
+
+impl Snapshot for Cucumber {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(Cucumber::Empty),
+            1 => Some(Cucumber::East),
+            2 => Some(Cucumber::South),
+            _ => None,
+        }
+    }
+}
+
+#[aoc_generator(day25)]
+pub fn input_generator(input: &str) -> Result<Grid<Cucumber>, ParseError> {
+    Grid::parse(lines(25, input), |c| match c {
+        '>' => Some(Cucumber::East),
+        'v' => Some(Cucumber::South),
+        '.' => Some(Cucumber::Empty),
+        _ => None,
+    })
+}
+
+/// Moves every cucumber of the given herd at once, returning whether any of them moved
+fn move_herd(grid: &mut Grid<Cucumber>, herd: Cucumber) -> bool {
+    let (m, n) = (grid.height(), grid.width());
+    let moves: Vec<_> = grid
+        .positions()
+        .filter(|&pos| grid[pos] == herd)
+        .map(|(row, col)| match herd {
+            Cucumber::East => ((row, col), (row, (col + 1) % n)),
+            _ => ((row, col), ((row + 1) % m, col)),
+        })
+        .filter(|&(_, to)| grid[to] == Cucumber::Empty)
+        .collect();
+    for &(from, to) in &moves {
+        grid[from] = Cucumber::Empty;
+        grid[to] = herd;
+    }
+    !moves.is_empty()
+}
+
+/// First step on which no sea cucumber moves, giving up after `limit` steps
+pub(crate) fn settle(grid: &Grid<Cucumber>, limit: usize) -> Option<u64> {
+    let mut grid = grid.clone();
+    for step in 1..=limit {
+        limits::step();
+        let east = move_herd(&mut grid, Cucumber::East);
+        let south = move_herd(&mut grid, Cucumber::South);
+        if !east && !south {
+            return Some(step as u64);
+        }
+    }
+    None
+}
+
+#[aoc(day25, part1, day25_1)]
+pub fn part1(grid: &Grid<Cucumber>) -> u64 {
+    settle(grid, usize::MAX).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const SAMPLE: &str = "v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>";
+
+    #[test]
+    fn test_part_1() {
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input), 58);
+    }
+}
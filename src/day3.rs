@@ -1,13 +1,115 @@
+use crate::parse::{lines, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::fmt;
 
-fn get_frequency(nums: &[u32], bits: usize) -> Vec<(u32, u32)> {
-    let mut counts = vec![(0, 0); bits];
-    for &num in nums {
-        for pos in 0..bits {
-            if (num & (1 << pos)) == 0 {
-                counts[pos as usize].0 += 1;
+/// A reading, or any number, of any width. Bit `pos` from the right is in word `pos / 64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bits {
+    words: Vec<u64>,
+}
+
+impl Bits {
+    pub fn zero(width: usize) -> Self {
+        Self {
+            words: vec![0; width.div_ceil(64).max(1)],
+        }
+    }
+
+    pub fn get(&self, pos: usize) -> bool {
+        self.words[pos / 64] & (1 << (pos % 64)) != 0
+    }
+
+    pub fn set(&mut self, pos: usize) {
+        self.words[pos / 64] |= 1 << (pos % 64);
+    }
+
+    /// The number when it fits in a `u64`
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.words[..] {
+            [val, ref rest @ ..] if rest.iter().all(|&w| w == 0) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn product(&self, other: &Bits) -> Bits {
+        let mut words = vec![0_u64; self.words.len() + other.words.len()];
+        for (i, &a) in self.words.iter().enumerate() {
+            let mut carry = 0_u128;
+            for (j, &b) in other.words.iter().enumerate() {
+                let val = a as u128 * b as u128 + words[i + j] as u128 + carry;
+                words[i + j] = val as u64;
+                carry = val >> 64;
+            }
+            words[i + other.words.len()] = carry as u64;
+        }
+        while words.len() > 1 && words.last() == Some(&0) {
+            words.pop();
+        }
+        Bits { words }
+    }
+}
+
+/// Decimal digits, worked out 19 at a time
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", decimal(self.words.clone()))
+    }
+}
+
+fn decimal(mut words: Vec<u64>) -> String {
+    const CHUNK: u64 = 10_000_000_000_000_000_000;
+    let mut chunks = Vec::new();
+    while words.iter().any(|&w| w != 0) {
+        let mut rem = 0_u128;
+        for word in words.iter_mut().rev() {
+            let val = (rem << 64) | *word as u128;
+            *word = (val / CHUNK as u128) as u64;
+            rem = val % CHUNK as u128;
+        }
+        chunks.push(rem as u64);
+    }
+    let mut chunks = chunks.into_iter().rev();
+    let first = chunks.next().unwrap_or(0).to_string();
+    chunks.fold(first, |text, chunk| format!("{}{:019}", text, chunk))
+}
+
+/// The diagnostic report, every reading `width` bits wide
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub width: usize,
+    pub readings: Vec<Bits>,
+}
+
+impl Snapshot for Report {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.width.encode(out);
+        self.readings.len().encode(out);
+        for reading in &self.readings {
+            reading.words.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let width = usize::decode(input)?;
+        let readings = Vec::<Vec<u64>>::decode(input)?;
+        let words = width.div_ceil(64).max(1);
+        readings.iter().all(|r| r.len() == words).then(|| Report {
+            width,
+            readings: readings.into_iter().map(|words| Bits { words }).collect(),
+        })
+    }
+}
+
+/// Readings with a zero and with a one at every position
+fn get_frequency(readings: &[Bits], width: usize) -> Vec<(usize, usize)> {
+    let mut counts = vec![(0, 0); width];
+    for reading in readings {
+        for (pos, count) in counts.iter_mut().enumerate() {
+            if reading.get(pos) {
+                count.1 += 1;
             } else {
-                counts[pos as usize].1 += 1;
+                count.0 += 1;
             }
         }
     }
@@ -15,78 +117,122 @@ fn get_frequency(nums: &[u32], bits: usize) -> Vec<(u32, u32)> {
 }
 
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> (Vec<u32>, usize) {
-    let mut len = 0;
-    let nums: Vec<_> = input
-        .lines()
-        .map(|n| {
-            len = n.len();
-            u32::from_str_radix(n, 2).unwrap()
-        })
-        .collect();
-    assert!(!nums.is_empty());
-    (nums, len)
+pub fn input_generator(input: &str) -> Result<Report, ParseError> {
+    let mut width = None;
+    let mut readings = Vec::new();
+    for l in lines(3, input) {
+        let expected = *width.get_or_insert(l.text.len());
+        if l.text.len() != expected {
+            return Err(l.error(format!(
+                "expected {} bits, found {}",
+                expected,
+                l.text.len()
+            )));
+        }
+        let mut reading = Bits::zero(expected);
+        for (i, c) in l.text.char_indices() {
+            match c {
+                '0' => {}
+                '1' => reading.set(expected - 1 - i),
+                _ => return Err(l.error_at(&l.text[i..], "expected a binary digit")),
+            }
+        }
+        readings.push(reading);
+    }
+    match width {
+        Some(width) => Ok(Report { width, readings }),
+        None => Err(ParseError::eof(3, input, "empty diagnostic report")),
+    }
 }
 
 #[aoc(day3, part1, d31)]
-pub fn part1(input: &(Vec<u32>, usize)) -> u32 {
-    let (nums, bits) = input;
-    let bits = *bits;
-    let counts = get_frequency(&nums, 12);
-    let mut gamma = 0;
-    let mut epsilon = 0;
-    for pos in 0..bits {
-        if counts[pos].1 > counts[pos].0 {
-            gamma |= 1 << pos;
+pub fn part1(report: &Report) -> Bits {
+    let counts = get_frequency(&report.readings, report.width);
+    let mut gamma = Bits::zero(report.width);
+    let mut epsilon = Bits::zero(report.width);
+    for (pos, count) in counts.iter().enumerate() {
+        if count.1 > count.0 {
+            gamma.set(pos);
         } else {
-            epsilon |= 1 << pos;
+            epsilon.set(pos);
         }
     }
-    gamma * epsilon
+    gamma.product(&epsilon)
 }
 
-#[aoc(day3, part2, d32)]
-pub fn part2(input: &(Vec<u32>, usize)) -> u32 {
-    let (nums, bits) = input;
-    let bits = *bits;
-    let (mut o2, mut co2) = (nums.clone().to_owned(), nums.clone().to_owned());
-    for pos in (0..bits).rev() {
-        let counts = get_frequency(&o2, bits);
-        o2 = o2
-            .into_iter()
-            .filter(|n| {
-                if counts[pos].1 >= counts[pos].0 {
-                    return n & (1 << pos) != 0;
-                } else {
-                    return n & (1 << pos) == 0;
-                }
-            })
-            .collect();
-        if o2.len() <= 1 {
-            break;
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    /// Readings that start with the prefix of this node
+    count: usize,
+    children: [Option<usize>; 2],
+}
+
+/// Prefix tree of the readings from their highest bit down, which answers a bit criterion with a
+/// single walk instead of filtering the readings again at every position
+#[derive(Debug, Clone)]
+pub struct Trie {
+    width: usize,
+    /// The root, the empty prefix, comes first
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    pub fn new(report: &Report) -> Self {
+        let mut nodes = vec![Node::default()];
+        for reading in &report.readings {
+            let mut node = 0;
+            nodes[node].count += 1;
+            for pos in (0..report.width).rev() {
+                let bit = reading.get(pos) as usize;
+                node = match nodes[node].children[bit] {
+                    Some(child) => child,
+                    None => {
+                        nodes.push(Node::default());
+                        nodes[node].children[bit] = Some(nodes.len() - 1);
+                        nodes.len() - 1
+                    }
+                };
+                nodes[node].count += 1;
+            }
+        }
+        Self {
+            width: report.width,
+            nodes,
         }
     }
 
-    for pos in (0..bits).rev() {
-        let counts = get_frequency(&co2, bits);
-        co2 = co2
-            .into_iter()
-            .filter(|n| {
-                if counts[pos].1 < counts[pos].0 {
-                    return n & (1 << pos) != 0;
-                } else {
-                    return n & (1 << pos) == 0;
-                }
-            })
-            .collect();
-        if co2.len() <= 1 {
-            break;
+    fn count(&self, child: Option<usize>) -> usize {
+        child.map_or(0, |c| self.nodes[c].count)
+    }
+
+    /// Follows the bit `criterion` picks from the number of readings with a zero and with a one at
+    /// the next position, down to a full reading. `None` when it picks a bit no reading has.
+    pub fn select<F: Fn(usize, usize) -> bool>(&self, criterion: F) -> Option<Bits> {
+        let mut reading = Bits::zero(self.width);
+        let mut node = 0;
+        for pos in (0..self.width).rev() {
+            let [zero, one] = self.nodes[node].children;
+            let bit = criterion(self.count(zero), self.count(one));
+            node = self.nodes[node].children[bit as usize]?;
+            if bit {
+                reading.set(pos);
+            }
         }
+        Some(reading)
     }
-    assert!(!o2.is_empty(), "o2 is empty");
-    assert!(!co2.is_empty(), "co2 is empty");
-    let (o2, co2) = (o2[0], co2[0]);
-    o2 * co2
+}
+
+#[aoc(day3, part2, d32)]
+pub fn part2(report: &Report) -> Bits {
+    let trie = Trie::new(report);
+    let o2 = trie
+        .select(|zeros, ones| ones >= zeros)
+        .expect("o2 is empty");
+    // a bit every remaining number shares is not a criterion at all
+    let co2 = trie
+        .select(|zeros, ones| (ones < zeros && ones > 0) || zeros == 0)
+        .expect("co2 is empty");
+    o2.product(&co2)
 }
 
 #[cfg(test)]
@@ -95,15 +241,85 @@ mod tests {
     const SAMPLE: &str =
         "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
 
+    /// Readings of the sample padded on the left with `pad` copies of the same bit
+    fn widened(pad: usize, bit: char) -> String {
+        let prefix: String = std::iter::repeat_n(bit, pad).collect();
+        SAMPLE
+            .lines()
+            .map(|l| format!("{}{}", prefix, l))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part1(&input), 198);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part1(&input).to_u64(), Some(198));
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
-        assert_eq!(part2(&input), 230);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input).to_u64(), Some(230));
+        let input = input_generator("100\n110\n111").unwrap();
+        assert_eq!(part2(&input).to_u64(), Some(28));
+    }
+
+    #[test]
+    fn test_trie() {
+        let report = input_generator(SAMPLE).unwrap();
+        let trie = Trie::new(&report);
+        assert_eq!(trie.nodes[0].count, 12);
+        let value = |bits: Bits| bits.words[0];
+        // always the smaller or the larger branch that has any readings
+        let lowest = trie.select(|zeros, _| zeros == 0).unwrap();
+        let highest = trie.select(|_, ones| ones > 0).unwrap();
+        assert_eq!((value(lowest), value(highest)), (0b00010, 0b11110));
+        assert_eq!(trie.select(|_, _| true), None);
+        let empty = Trie::new(&Report {
+            width: 3,
+            readings: Vec::new(),
+        });
+        assert_eq!(empty.select(|zeros, ones| ones >= zeros), None);
+    }
+
+    #[test]
+    fn test_widths() {
+        // leading zeros change nothing
+        let input = input_generator(&widened(7, '0')).unwrap();
+        assert_eq!(input.width, 12);
+        assert_eq!(part1(&input).to_u64(), Some(22 * (9 | 0x7f << 5)));
+        assert_eq!(part2(&input).to_u64(), Some(230));
+
+        // the top 59 bits are all ones, so gamma is 2^64 - 1 - 9 and epsilon 9
+        let input = input_generator(&widened(59, '1')).unwrap();
+        assert_eq!(input.width, 64);
+        let gamma_epsilon = part1(&input);
+        assert_eq!(gamma_epsilon.to_u64(), None);
+        assert_eq!(gamma_epsilon.to_string(), "166020696663385964454");
+        let rating = |low: u64| u64::MAX - 31 + low;
+        let expected = rating(23) as u128 * rating(10) as u128;
+        assert_eq!(part2(&input).to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_wide() {
+        let ones = "1".repeat(100);
+        let input = input_generator(&format!("{}\n{}", ones, ones)).unwrap();
+        assert_eq!(input.readings[0].words, [u64::MAX, (1 << 36) - 1]);
+        assert_eq!(part1(&input).to_u64(), Some(0));
+        // (2^100 - 1)^2
+        let expected = "1606938044258990275541962092338627301321746534979799428890625";
+        assert_eq!(part2(&input).to_string(), expected);
+        assert_eq!(decimal(vec![0, 1]), "18446744073709551616");
+        assert_eq!(decimal(vec![0]), "0");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(input_generator("").is_err());
+        assert!(input_generator("101\n11").is_err());
+        let err = input_generator("10\n1x").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
     }
 }
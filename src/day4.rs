@@ -1,3 +1,5 @@
+use crate::parse::{lines, Line, ParseError};
+use crate::snapshot::Snapshot;
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[derive(Clone, Copy)]
@@ -7,28 +9,25 @@ pub struct Card {
 }
 
 impl Card {
-    fn new(input: &[&str]) -> Self {
+    fn new(input: &[Line]) -> Result<Self, ParseError> {
         let mut board = [[(0, false); 5]; 5];
         let mut remaining = 0;
-        for row in 0..5 {
-            let nums: Vec<u32> = input[row]
-                .trim()
-                .split(" ")
-                .filter_map(|n| {
-                    if n.trim().is_empty() {
-                        None
-                    } else {
-                        Some(n.parse().unwrap())
-                    }
-                })
-                .collect();
+        for (row, line) in input.iter().enumerate() {
+            let nums = line
+                .text
+                .split_whitespace()
+                .map(|n| line.parse(n))
+                .collect::<Result<Vec<u32>, _>>()?;
+            if nums.len() != 5 {
+                return Err(line.error(format!("expected 5 numbers, found {}", nums.len())));
+            }
 
-            for col in 0..5 {
-                remaining += nums[col];
-                board[row][col].0 = nums[col];
+            for (col, &num) in nums.iter().enumerate() {
+                remaining += num;
+                board[row][col].0 = num;
             }
         }
-        Self { board, remaining }
+        Ok(Self { board, remaining })
     }
 
     fn mark(&mut self, num: u32) -> Option<u32> {
@@ -47,13 +46,13 @@ impl Card {
         if let (Some(r), Some(c)) = (fr, fc) {
             let (mut r_win, mut c_win) = (true, true);
             for row in 0..5 {
-                if self.board[row][c].1 == false {
+                if !self.board[row][c].1 {
                     r_win = false;
                     break;
                 }
             }
             for col in 0..5 {
-                if self.board[r][col].1 == false {
+                if !self.board[r][col].1 {
                     c_win = false;
                     break;
                 }
@@ -66,15 +65,34 @@ impl Card {
     }
 }
 
+impl Snapshot for Card {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.board.encode(out);
+        self.remaining.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let board = Snapshot::decode(input)?;
+        let remaining = Snapshot::decode(input)?;
+        Some(Self { board, remaining })
+    }
+}
+
 #[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> (Vec<u32>, Vec<Card>) {
-    let lines: Vec<&str> = input.lines().collect();
-    let picks: Vec<u32> = lines[0].split(",").map(|n| n.parse().unwrap()).collect();
+pub fn input_generator(input: &str) -> Result<(Vec<u32>, Vec<Card>), ParseError> {
+    let lines: Vec<_> = lines(4, input).collect();
+    let first = lines
+        .first()
+        .ok_or_else(|| ParseError::eof(4, input, "missing bingo picks"))?;
+    let picks = first.list(first.text, ",")?;
     let mut cards = Vec::new();
     for i in (2..lines.len()).step_by(6) {
-        cards.push(Card::new(&lines[i..i + 5]));
+        let rows = lines
+            .get(i..i + 5)
+            .ok_or_else(|| ParseError::eof(4, input, "truncated bingo card"))?;
+        cards.push(Card::new(rows)?);
     }
-    (picks, cards)
+    Ok((picks, cards))
 }
 
 #[aoc(day4, part1, d41)]
@@ -94,22 +112,18 @@ pub fn part1(input: &(Vec<u32>, Vec<Card>)) -> u32 {
 pub fn part2(input: &(Vec<u32>, Vec<Card>)) -> u32 {
     let (picks, mut boards) = (&input.0, input.1.clone());
 
+    // several boards can win on the same pick, in which case the last one of them counts
+    let mut last = None;
     for &pick in picks {
-        if boards.len() == 1 {
-            if let Some(r) = boards[0].mark(pick) {
-                return r * pick;
+        boards.retain_mut(|board| match board.mark(pick) {
+            Some(r) => {
+                last = Some(r * pick);
+                false
             }
-        } else {
-            boards = boards
-                .into_iter()
-                .filter_map(|mut b| {
-                    if b.mark(pick).is_none() {
-                        Some(b)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+            None => true,
+        });
+        if boards.is_empty() {
+            return last.unwrap();
         }
     }
     unreachable!("Bingo was never reached");
@@ -140,13 +154,16 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 4512);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(part2(&input), 1924);
+        let last_card = &SAMPLE[SAMPLE.rfind("\n\n").unwrap()..];
+        let input = input_generator(&format!("{}{}", SAMPLE, last_card)).unwrap();
         assert_eq!(part2(&input), 1924);
     }
 }
@@ -1,94 +1,66 @@
+use crate::parse::{lines, Line, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::HashSet;
 
 pub type Coord = (usize, usize);
 
+fn parse_coord(line: &Line, part: &str) -> Result<Coord, ParseError> {
+    let (x, y) = line.split_once(part, ",")?;
+    Ok((line.parse(x)?, line.parse(y)?))
+}
+
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<(Coord, Coord)> {
-    input
-        .lines()
+pub fn input_generator(input: &str) -> Result<Vec<(Coord, Coord)>, ParseError> {
+    lines(5, input)
         .map(|line| {
-            line.split_once(" -> ")
-                .map(|(s, e)| {
-                    let start = s
-                        .split_once(",")
-                        .map(|(ss, se)| {
-                            (ss.parse::<usize>().unwrap(), se.parse::<usize>().unwrap())
-                        })
-                        .unwrap();
-                    let end = e
-                        .split_once(",")
-                        .map(|(es, ee)| {
-                            (es.parse::<usize>().unwrap(), ee.parse::<usize>().unwrap())
-                        })
-                        .unwrap();
-                    (start, end)
-                })
-                .unwrap()
+            let (start, end) = line.split_once(line.text, " -> ")?;
+            let (start, end) = (parse_coord(&line, start)?, parse_coord(&line, end)?);
+            let (dx, dy) = (start.0.abs_diff(end.0), start.1.abs_diff(end.1));
+            if dx != 0 && dy != 0 && dx != dy {
+                return Err(line.error("lines have to be horizontal, vertical or at 45 degrees"));
+            }
+            Ok((start, end))
         })
         .collect()
 }
 
-#[aoc(day5, part1, d51)]
-pub fn part1(input: &Vec<(Coord, Coord)>) -> usize {
+/// Every point covered by the line, diagonal lines only being followed when `diagonals` is set
+fn points(&(start, end): &(Coord, Coord), diagonals: bool) -> Vec<Coord> {
     let mut coords = Vec::new();
-    let mut positions = HashSet::new();
-    let mut overlap = HashSet::new();
-    for &(start, end) in input {
-        if start.0 == end.0 {
-            for y in start.1.min(end.1)..=start.1.max(end.1) {
-                coords.push((start.0, y));
-            }
-        } else if start.1 == end.1 {
-            for x in start.0.min(end.0)..=start.0.max(end.0) {
-                coords.push((x, start.1));
-            }
+    if start.0 == end.0 {
+        for y in start.1.min(end.1)..=start.1.max(end.1) {
+            coords.push((start.0, y));
         }
-    }
-    for coord in coords {
-        if positions.contains(&coord) {
-            overlap.insert(coord);
+    } else if start.1 == end.1 {
+        for x in start.0.min(end.0)..=start.0.max(end.0) {
+            coords.push((x, start.1));
+        }
+    } else if diagonals {
+        let (mut x, mut y) = start;
+        loop {
+            coords.push((x, y));
+            if x == end.0 && y == end.1 {
+                break;
+            }
+            if x < end.0 {
+                x += 1;
+            } else {
+                x -= 1;
+            };
+            if y < end.1 {
+                y += 1;
+            } else {
+                y -= 1;
+            };
         }
-        positions.insert(coord);
     }
-    overlap.len()
+    coords
 }
 
-#[aoc(day5, part2, d52)]
-pub fn part2(input: &Vec<(Coord, Coord)>) -> usize {
-    let mut coords = Vec::new();
+fn overlaps_hashed(input: &[(Coord, Coord)], diagonals: bool) -> usize {
     let mut positions = HashSet::new();
     let mut overlap = HashSet::new();
-    for &(start, end) in input {
-        if start.0 == end.0 {
-            for y in start.1.min(end.1)..=start.1.max(end.1) {
-                coords.push((start.0, y));
-            }
-        } else if start.1 == end.1 {
-            for x in start.0.min(end.0)..=start.0.max(end.0) {
-                coords.push((x, start.1));
-            }
-        } else {
-            let (mut x, mut y) = start;
-            loop {
-                coords.push((x, y));
-                if x == end.0 && y == end.1 {
-                    break;
-                }
-                if x < end.0 {
-                    x += 1;
-                } else {
-                    x -= 1;
-                };
-                if y < end.1 {
-                    y += 1;
-                } else {
-                    y -= 1;
-                };
-            }
-        }
-    }
-    for coord in coords {
+    for coord in input.iter().flat_map(|line| points(line, diagonals)) {
         if positions.contains(&coord) {
             overlap.insert(coord);
         }
@@ -97,6 +69,50 @@ pub fn part2(input: &Vec<(Coord, Coord)>) -> usize {
     overlap.len()
 }
 
+/// Cells of the largest grid `overlaps_dense` allocates, about ten times the area of a real input
+const DENSE_CELLS: usize = 1 << 24;
+
+/// Same as `overlaps_hashed` but counts on a dense grid spanning every line. Lines too far out for
+/// such a grid are counted by `overlaps_hashed` instead.
+fn overlaps_dense(input: &[(Coord, Coord)], diagonals: bool) -> usize {
+    let x_max = input.iter().map(|(s, e)| s.0.max(e.0)).max().unwrap_or(0);
+    let y_max = input.iter().map(|(s, e)| s.1.max(e.1)).max().unwrap_or(0);
+    let size = x_max.checked_add(1).zip(y_max.checked_add(1));
+    let Some((width, height)) = size.filter(|&(w, h)| w.saturating_mul(h) <= DENSE_CELLS) else {
+        return overlaps_hashed(input, diagonals);
+    };
+    let mut counts = vec![0_u8; width * height];
+    let mut overlap = 0;
+    for (x, y) in input.iter().flat_map(|line| points(line, diagonals)) {
+        let count = &mut counts[y * width + x];
+        if *count == 1 {
+            overlap += 1;
+        }
+        *count = count.saturating_add(1);
+    }
+    overlap
+}
+
+#[aoc(day5, part1, d51)]
+pub fn part1(input: &[(Coord, Coord)]) -> usize {
+    overlaps_hashed(input, false)
+}
+
+#[aoc(day5, part1, d51_dense)]
+pub fn part1_dense(input: &[(Coord, Coord)]) -> usize {
+    overlaps_dense(input, false)
+}
+
+#[aoc(day5, part2, d52)]
+pub fn part2(input: &[(Coord, Coord)]) -> usize {
+    overlaps_hashed(input, true)
+}
+
+#[aoc(day5, part2, d52_dense)]
+pub fn part2_dense(input: &[(Coord, Coord)]) -> usize {
+    overlaps_dense(input, true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,13 +129,35 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 5);
+        assert_eq!(part1_dense(&input), 5);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 12);
+        assert_eq!(part2_dense(&input), 12);
+    }
+
+    #[test]
+    fn test_far_lines() {
+        let far = usize::MAX - 1;
+        let input = format!("0,0 -> 0,3\n0,2 -> 2,2\n{0},{0} -> {0},{1}", far, far - 1);
+        let input = input_generator(&input).unwrap();
+        assert_eq!(part1_dense(&input), 1);
+        assert_eq!(part2_dense(&input), part2(&input));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let err = input_generator("0,0 -> 2,2\n0,0 -> 3,1").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.reason,
+            "lines have to be horizontal, vertical or at 45 degrees"
+        );
+        assert!(input_generator("3,1 -> 0,4\n5,5 -> 5,5").is_ok());
     }
 }
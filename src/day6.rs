@@ -1,51 +1,98 @@
+use crate::parse::{lines, ParseError};
+use crate::simulation::Simulation;
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> [u64; 9] {
+pub fn input_generator(input: &str) -> Result<[u64; 9], ParseError> {
     let mut fish_count = [0_u64; 9];
-    for fish in input.split(",") {
-        let day: usize = fish.parse().unwrap();
-        fish_count[day] += 1;
+    for line in lines(6, input) {
+        for fish in line.text.split(',') {
+            let day: usize = line.parse(fish)?;
+            if day > 8 {
+                return Err(line.error_at(fish, format!("timer {} is out of range", day)));
+            }
+            fish_count[day] += 1;
+        }
     }
-    fish_count
+    Ok(fish_count)
+}
+
+/// Number of fish per timer value
+#[derive(Debug, Clone)]
+struct School([u64; 9]);
+
+impl Simulation for School {
+    fn step(&mut self) {
+        let reset = self.0[0];
+        self.0.rotate_left(1);
+        self.0[6] += reset;
+    }
+}
+
+pub fn fish_after(input: &[u64; 9], days: usize) -> u64 {
+    let mut school = School(*input);
+    school.run(days);
+    school.0.iter().sum()
 }
 
 #[aoc(day6, part1, d61)]
 pub fn part1(input: &[u64; 9]) -> u64 {
-    let mut count = input.clone();
-    for _ in 0..80 {
-        let reset = count[0];
-        count.rotate_left(1);
-        count[6] += reset;
-    }
-    count.into_iter().sum()
+    fish_after(input, 80)
 }
 
 #[aoc(day6, part2, d62)]
 pub fn part2(input: &[u64; 9]) -> u64 {
-    let mut count = input.clone();
-    for _ in 0..256 {
-        let reset = count[0];
-        count.rotate_left(1);
-        count[6] += reset;
-    }
-    count.into_iter().sum()
+    fish_after(input, 256)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth::Rng;
     const SAMPLE: &str = "3,4,3,1,2";
 
+    /// Keeps every lanternfish in a list, the way the puzzle describes them
+    fn brute_force(timers: &[u8], days: usize) -> u64 {
+        let mut fish = timers.to_vec();
+        for _ in 0..days {
+            let spawned = fish.iter().filter(|&&t| t == 0).count();
+            for timer in &mut fish {
+                *timer = if *timer == 0 { 6 } else { *timer - 1 };
+            }
+            fish.extend(std::iter::repeat_n(8, spawned));
+        }
+        fish.len() as u64
+    }
+
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
+        assert_eq!(fish_after(&input, 18), 26);
         assert_eq!(part1(&input), 5934);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 26984457539);
     }
+
+    #[test]
+    fn test_brute_force() {
+        let mut rng = Rng::new(6);
+        for _ in 0..100 {
+            let timers: Vec<_> = (0..rng.range(1..=6))
+                .map(|_| rng.range(0..=8) as u8)
+                .collect();
+            let days = rng.below(70);
+            let input = timers.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+            let input = input_generator(&input.join(",")).unwrap();
+            assert_eq!(
+                fish_after(&input, days),
+                brute_force(&timers, days),
+                "{:?}",
+                timers
+            );
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::parse::{lines, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 
 fn get_min_fuel<F: FnMut(i64) -> i64>(positions: &[i64], mut calc: F) -> i64 {
@@ -13,8 +14,15 @@ fn get_min_fuel<F: FnMut(i64) -> i64>(positions: &[i64], mut calc: F) -> i64 {
 }
 
 #[aoc_generator(day7)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(",").map(|n| n.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, ParseError> {
+    let mut positions = Vec::new();
+    for line in lines(7, input) {
+        positions.extend(line.list::<i64>(line.text, ",")?);
+    }
+    if positions.is_empty() {
+        return Err(ParseError::eof(7, input, "no crab positions"));
+    }
+    Ok(positions)
 }
 
 #[aoc(day7, part1, d71)]
@@ -33,6 +41,34 @@ pub fn part2(input: &[i64]) -> i64 {
     get_min_fuel(input, calc)
 }
 
+/// The total distance is minimal at the median
+#[aoc(day7, part1, d71_median)]
+pub fn part1_median(input: &[i64]) -> i64 {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2];
+    input.iter().map(|p| (p - median).abs()).sum()
+}
+
+/// The triangular cost is minimal within half a step of the mean, so only the two integers
+/// around it need checking
+#[aoc(day7, part2, d72_mean)]
+pub fn part2_mean(input: &[i64]) -> i64 {
+    let mean = input.iter().sum::<i64>().div_euclid(input.len() as i64);
+    (mean..=mean + 1)
+        .map(|target| {
+            input
+                .iter()
+                .map(|p| {
+                    let n = (p - target).abs();
+                    n * (n + 1) / 2
+                })
+                .sum()
+        })
+        .min()
+        .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,13 +76,15 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 37);
+        assert_eq!(part1_median(&input), 37);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 168);
+        assert_eq!(part2_mean(&input), 168);
     }
 }
@@ -1,42 +1,45 @@
+use crate::parse::{lines, Line, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::{hash_map::Entry, HashMap};
 
+/// Signal patterns and output digits of every display
+pub type Notes = (Vec<Vec<String>>, Vec<Vec<String>>);
+
 #[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+pub fn input_generator(input: &str) -> Result<Notes, ParseError> {
     let mut wires = Vec::new();
     let mut digits = Vec::new();
-    for line in input.lines() {
-        let mut parts = line.split("|");
-        let wire: Vec<_> = parts
-            .next()
-            .unwrap()
-            .split(" ")
-            .filter(|n| !n.is_empty())
-            .map(|n| n.to_string())
-            .collect();
-        let digit: Vec<_> = parts
-            .next()
-            .unwrap()
-            .split(" ")
-            .filter(|n| !n.is_empty())
-            .map(|n| n.to_string())
-            .collect();
-        wires.push(wire);
-        digits.push(digit);
+    for line in lines(8, input) {
+        let (wire, digit) = line.split_once(line.text, "|")?;
+        wires.push(parse_patterns(&line, wire, 10)?);
+        digits.push(parse_patterns(&line, digit, 4)?);
     }
-    (wires, digits)
+    Ok((wires, digits))
+}
+
+fn parse_patterns(line: &Line, part: &str, count: usize) -> Result<Vec<String>, ParseError> {
+    let patterns: Vec<_> = part.split_whitespace().collect();
+    if patterns.len() != count {
+        return Err(line.error_at(
+            part,
+            format!("expected {} patterns, found {}", count, patterns.len()),
+        ));
+    }
+    for pattern in &patterns {
+        if let Some(i) = pattern.find(|c| !('a'..='g').contains(&c)) {
+            return Err(line.error_at(&pattern[i..], "expected a segment between `a` and `g`"));
+        }
+    }
+    Ok(patterns.into_iter().map(|n| n.to_string()).collect())
 }
 
 #[aoc(day8, part1, d81)]
-pub fn part1<'a>(input: &(Vec<Vec<String>>, Vec<Vec<String>>)) -> u64 {
+pub fn part1(input: &Notes) -> u64 {
     let (_, digits) = input;
     digits
         .iter()
         .flat_map(|n| n.iter())
-        .filter(|n| match n.len() {
-            2 | 3 | 4 | 7 => true,
-            _ => false,
-        })
+        .filter(|n| matches!(n.len(), 2 | 3 | 4 | 7))
         .count() as u64
 }
 
@@ -45,7 +48,7 @@ const VALID: [&str; 10] = [
 ];
 
 #[aoc(day8, part2, d82)]
-pub fn part2<'a>(input: &(Vec<Vec<String>>, Vec<Vec<String>>)) -> u64 {
+pub fn part2(input: &Notes) -> u64 {
     let (wires, output) = input;
     let valid_mapping = to_frequency(VALID.iter().flat_map(|n| n.chars()));
     let mut lookup = HashMap::new();
@@ -74,7 +77,7 @@ pub fn part2<'a>(input: &(Vec<Vec<String>>, Vec<Vec<String>>)) -> u64 {
 fn to_frequency<I: IntoIterator<Item = char>>(iter: I) -> [u8; 7] {
     let mut occurences = [0; 7];
     for c in iter {
-        let idx = (c as u8 - 'a' as u8) as usize;
+        let idx = (c as u8 - b'a') as usize;
         occurences[idx] += 1;
     }
     occurences
@@ -89,14 +92,14 @@ fn resolve_mapping(valid: [u8; 7], mapping: [u8; 7], parts: &[String]) -> HashMa
                 o.remove_entry();
             }
             Entry::Vacant(e) => {
-                e.insert((i as u8 + 'a' as u8) as char);
+                e.insert((i as u8 + b'a') as char);
             }
         }
     }
     let mut out = HashMap::new();
     for (i, count) in mapping.iter().enumerate() {
         if let Some(&c) = valid_map.get(count) {
-            let cur_char = (i as u8 + 'a' as u8) as char;
+            let cur_char = (i as u8 + b'a') as char;
             out.insert(cur_char, c);
         }
     }
@@ -117,7 +120,7 @@ fn resolve_mapping(valid: [u8; 7], mapping: [u8; 7], parts: &[String]) -> HashMa
                 .iter()
                 .enumerate()
                 .find_map(|(i, count)| {
-                    let cc = (i as u8 + 'a' as u8) as char;
+                    let cc = (i as u8 + b'a') as char;
                     if *count == 8 && cc != sc {
                         Some(cc)
                     } else {
@@ -131,13 +134,13 @@ fn resolve_mapping(valid: [u8; 7], mapping: [u8; 7], parts: &[String]) -> HashMa
 
     // Find d and g
     let four = parts.iter().find(|n| n.len() == 4).unwrap();
-    let d = four.chars().find(|c| !out.contains_key(&c)).unwrap();
+    let d = four.chars().find(|c| !out.contains_key(c)).unwrap();
     out.insert(d, 'd');
     let g = mapping
         .iter()
         .enumerate()
         .find_map(|(i, count)| {
-            let cc = (i as u8 + 'a' as u8) as char;
+            let cc = (i as u8 + b'a') as char;
             if *count == 7 && cc != d {
                 Some(cc)
             } else {
@@ -166,13 +169,13 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 26);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 61229);
     }
 }
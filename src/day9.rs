@@ -1,82 +1,66 @@
+use crate::export::{Image, PALETTE};
+use crate::grid::{Grid, Position};
+use crate::parse::{lines, ParseError};
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[aoc_generator(day9)]
-pub fn input_generator(input: &str) -> Vec<Vec<u64>> {
-    input.lines().fold(Vec::new(), |mut acc, l| {
-        acc.push(
-            l.split("")
-                .filter(|c| !c.is_empty())
-                .map(|n| n.parse().unwrap())
-                .collect(),
-        );
-        acc
-    })
+pub fn input_generator(input: &str) -> Result<Grid<u8>, ParseError> {
+    Grid::from_digits(lines(9, input))
 }
 
-fn find_minimas(grid: &[Vec<u64>]) -> Vec<(usize, usize)> {
-    let (m, n) = (grid.len(), grid[0].len());
-    let mut minimas = Vec::new();
-    for row in 0..m {
-        for col in 0..n {
-            assert!(grid[row].len() == n);
-            let current = grid[row][col];
-            if row > 0 && grid[row - 1][col] <= current
-                || row + 1 < m && grid[row + 1][col] <= current
-                || col > 0 && grid[row][col - 1] <= current
-                || col + 1 < n && grid[row][col + 1] <= current
-            {
-                continue;
-            }
-            minimas.push((row, col));
-        }
-    }
-    minimas
+fn find_minimas(grid: &Grid<u8>) -> Vec<Position> {
+    grid.positions()
+        .filter(|&pos| {
+            grid.neighbours4(pos)
+                .all(|neighbour| grid[neighbour] > grid[pos])
+        })
+        .collect()
 }
 
 #[aoc(day9, part1, d91)]
-pub fn part1(grid: &[Vec<u64>]) -> u64 {
+pub fn part1(grid: &Grid<u8>) -> u64 {
     find_minimas(grid)
         .into_iter()
-        .map(|(row, col)| grid[row][col] + 1)
+        .map(|pos| grid[pos] as u64 + 1)
         .sum()
 }
 
-#[aoc(day9, part2, d92)]
-pub fn part2(grid: &[Vec<u64>]) -> u64 {
-    let mut grid = grid.to_owned();
-    let (m, n) = (grid.len(), grid[0].len());
-    let local_minima = find_minimas(&grid);
-
-    let mut result = Vec::new();
-
-    for (r, c) in local_minima {
-        let mut queue = vec![(r, c)];
-        let mut basin_size = 0;
-
-        while let Some((row, col)) = queue.pop() {
-            if grid[row][col] == 9 {
+/// Basin of every point, numbered in the order of their low points. Points of height 9 belong to
+/// none of them.
+fn basins(grid: &Grid<u8>) -> Grid<Option<usize>> {
+    let mut basins = Grid::filled(grid.width(), grid.height(), None);
+    for (i, minima) in find_minimas(grid).into_iter().enumerate() {
+        let mut queue = vec![minima];
+        while let Some(pos) = queue.pop() {
+            if grid[pos] == 9 || basins[pos].is_some() {
                 continue;
             }
-            grid[row][col] = 9;
-            basin_size += 1;
-            if row > 0 {
-                queue.push((row - 1, col))
-            }
-            if row + 1 < m {
-                queue.push((row + 1, col))
-            }
-            if col > 0 {
-                queue.push((row, col - 1))
-            }
-            if col + 1 < n {
-                queue.push((row, col + 1))
-            }
+            basins[pos] = Some(i);
+            queue.extend(grid.neighbours4(pos));
         }
-        result.push(basin_size);
+    }
+    basins
+}
+
+#[aoc(day9, part2, d92)]
+pub fn part2(grid: &Grid<u8>) -> u64 {
+    let mut result = vec![0; find_minimas(grid).len()];
+    for &basin in basins(grid).iter().flatten() {
+        result[basin] += 1;
     }
     result.sort();
     result.reverse();
-    result[0..3].into_iter().product()
+    result[0..3].iter().product()
+}
+
+/// Heights in shades of gray and the basins in colours, with the ridges of height 9 in black
+pub(crate) fn images(grid: &Grid<u8>) -> Vec<(&'static str, Image)> {
+    let heightmap = Image::graymap(grid, 9, |&height| height);
+    let basins = Image::pixmap(&basins(grid), |basin| match basin {
+        Some(i) => PALETTE[i % PALETTE.len()],
+        None => [0, 0, 0],
+    });
+    vec![("heightmap", heightmap), ("basins", basins)]
 }
 
 #[cfg(test)]
@@ -90,13 +74,13 @@ mod tests {
 
     #[test]
     fn test_part_1() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part1(&input), 15);
     }
 
     #[test]
     fn test_part_2() {
-        let input = input_generator(SAMPLE);
+        let input = input_generator(SAMPLE).unwrap();
         assert_eq!(part2(&input), 1134);
     }
 }
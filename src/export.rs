@@ -0,0 +1,156 @@
+//! Grid states saved as plain Netpbm images, the ASCII variants of PBM, PGM and PPM. They need no
+//! image library, most viewers open them and they diff like any other text file.
+
+use crate::grid::Grid;
+use crate::registry::{downcast, Input};
+use crate::{day13, day15, day2, day20, day9};
+use std::io::{self, Write};
+
+/// Longest line allowed by the plain formats
+const LINE_LENGTH: usize = 70;
+
+/// Distinct colours for the regions of a picture
+pub const PALETTE: [[u8; 3]; 8] = [
+    [230, 25, 75],
+    [60, 180, 75],
+    [255, 225, 25],
+    [0, 130, 200],
+    [245, 130, 48],
+    [145, 30, 180],
+    [70, 240, 240],
+    [240, 50, 230],
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Image {
+    /// Black pixels where the grid is set
+    Bitmap(Grid<bool>),
+    /// Shades of gray from black at 0 to white at the given maximum
+    Graymap(Grid<u8>, u8),
+    Pixmap(Grid<[u8; 3]>),
+}
+
+impl Image {
+    pub fn bitmap<T, F: FnMut(&T) -> bool>(grid: &Grid<T>, f: F) -> Self {
+        Image::Bitmap(grid.map(f))
+    }
+
+    pub fn graymap<T, F: FnMut(&T) -> u8>(grid: &Grid<T>, max: u8, f: F) -> Self {
+        Image::Graymap(grid.map(f), max)
+    }
+
+    pub fn pixmap<T, F: FnMut(&T) -> [u8; 3]>(grid: &Grid<T>, f: F) -> Self {
+        Image::Pixmap(grid.map(f))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Image::Bitmap(_) => "pbm",
+            Image::Graymap(..) => "pgm",
+            Image::Pixmap(_) => "ppm",
+        }
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (magic, width, height, max) = match self {
+            Image::Bitmap(grid) => ("P1", grid.width(), grid.height(), None),
+            Image::Graymap(grid, max) => ("P2", grid.width(), grid.height(), Some(*max)),
+            Image::Pixmap(grid) => ("P3", grid.width(), grid.height(), Some(u8::MAX)),
+        };
+        writeln!(out, "{}\n{} {}", magic, width, height)?;
+        if let Some(max) = max {
+            writeln!(out, "{}", max)?;
+        }
+        for row in 0..height {
+            let samples: Vec<String> = (0..width)
+                .flat_map(|col| match self {
+                    Image::Bitmap(grid) => vec![(grid[(row, col)] as u8).to_string()],
+                    Image::Graymap(grid, _) => vec![grid[(row, col)].to_string()],
+                    Image::Pixmap(grid) => grid[(row, col)].map(|c| c.to_string()).to_vec(),
+                })
+                .collect();
+            write_wrapped(out, &samples)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the samples of a row separated by spaces, starting a new line instead of going over
+/// `LINE_LENGTH`
+fn write_wrapped<W: Write>(out: &mut W, samples: &[String]) -> io::Result<()> {
+    let mut length = 0;
+    for sample in samples {
+        if length > 0 && length + 1 + sample.len() > LINE_LENGTH {
+            writeln!(out)?;
+            length = 0;
+        }
+        if length > 0 {
+            write!(out, " ")?;
+            length += 1;
+        }
+        write!(out, "{}", sample)?;
+        length += sample.len();
+    }
+    writeln!(out)
+}
+
+/// Named images of `day` on its parsed `input`, or `None` for a day without any
+pub fn images(day: u8, input: &Input) -> Option<Vec<(&'static str, Image)>> {
+    let images = match day {
+        2 => day2::images(downcast::<Vec<_>>(input)),
+        9 => day9::images(downcast(input)),
+        13 => day13::images(downcast(input)),
+        15 => day15::images(downcast(input)),
+        20 => day20::images(downcast(input)),
+        _ => return None,
+    };
+    Some(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    fn parse(day: u8, input: &str) -> Input {
+        registry::day(day).unwrap().generate(input).unwrap()
+    }
+
+    fn plain(image: &Image) -> String {
+        let mut out = Vec::new();
+        image.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write() {
+        let grid = Grid::new(3, 2, vec![0, 1, 2, 3, 4, 5]);
+        let image = Image::bitmap(&grid, |&n| n % 2 == 1);
+        assert_eq!(image.extension(), "pbm");
+        assert_eq!(plain(&image), "P1\n3 2\n0 1 0\n1 0 1\n");
+        let image = Image::graymap(&grid, 5, |&n| n);
+        assert_eq!(plain(&image), "P2\n3 2\n5\n0 1 2\n3 4 5\n");
+        let image = Image::pixmap(&grid, |&n| [n, 0, 255]);
+        assert_eq!(
+            plain(&image),
+            "P3\n3 2\n255\n0 0 255 1 0 255 2 0 255\n3 0 255 4 0 255 5 0 255\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let image = Image::pixmap(&Grid::filled(10, 1, [255, 255, 255]), |&c| c);
+        let text = plain(&image);
+        assert!(text.lines().all(|line| line.len() <= LINE_LENGTH));
+        assert_eq!(text.split_whitespace().count(), 4 + 30);
+    }
+
+    #[test]
+    fn test_images() {
+        let heightmap = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678";
+        let named = images(9, &parse(9, heightmap)).unwrap();
+        let names: Vec<_> = named.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["heightmap", "basins"]);
+        assert!(images(1, &parse(1, "199")).is_none());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::parse::{Line, ParseError};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+pub type Position = (usize, usize);
+
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+const ADJACENT: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Row-major 2D grid backed by a single `Vec`, indexed by `(row, col)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "grid size mismatch");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn from_fn<F: FnMut(Position) -> T>(width: usize, height: usize, mut f: F) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .map(&mut f)
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    /// Parses one row per line, mapping every character to a cell or rejecting it with `None`
+    pub fn parse<'a, I, F>(lines: I, mut f: F) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = Line<'a>>,
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut width = None;
+        let mut cells = Vec::new();
+        let mut height = 0;
+        for line in lines {
+            let before = cells.len();
+            for (i, c) in line.text.char_indices() {
+                let cell = f(c).ok_or_else(|| {
+                    line.error_at(&line.text[i..], format!("unexpected character `{}`", c))
+                })?;
+                cells.push(cell);
+            }
+            let len = cells.len() - before;
+            let expected = *width.get_or_insert(len);
+            if len != expected {
+                return Err(line.error(format!("expected {} cells, found {}", expected, len)));
+            }
+            height += 1;
+        }
+        Ok(Self::new(width.unwrap_or(0), height, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, (row, col): Position) -> bool {
+        row < self.height && col < self.width
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if self.contains((row, col)) {
+            Some(&self.cells[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        if self.contains((row, col)) {
+            Some(&mut self.cells[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+    /// Same as `get` but accepts coordinates that may lie left of or above the grid
+    pub fn get_signed(&self, row: i64, col: i64) -> Option<&T> {
+        let (row, col) = (usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        self.get(row, col)
+    }
+
+    pub fn set(&mut self, (row, col): Position, value: T) {
+        self[(row, col)] = value;
+    }
+
+    fn offsets(
+        &self,
+        (row, col): Position,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = Position> {
+        let (height, width) = (self.height, self.width);
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let (r, c) = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+            if r < height && c < width {
+                Some((r, c))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Positions directly above, left, right and below `pos` that lie within the grid
+    pub fn neighbours4(&self, pos: Position) -> impl Iterator<Item = Position> {
+        self.offsets(pos, &ORTHOGONAL)
+    }
+
+    /// Same as `neighbours4` but also includes diagonals
+    pub fn neighbours8(&self, pos: Position) -> impl Iterator<Item = Position> {
+        self.offsets(pos, &ADJACENT)
+    }
+
+    /// Every position in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "column out of bounds");
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |col| self.column(col))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self::new(width, height, vec![value; width * height])
+    }
+}
+
+impl Grid<u8> {
+    /// Parses a map of single digits such as `2199943210`
+    pub fn from_digits<'a, I: IntoIterator<Item = Line<'a>>>(lines: I) -> Result<Self, ParseError> {
+        Self::parse(lines, |c| c.to_digit(10).map(|d| d as u8))
+    }
+}
+
+impl Grid<bool> {
+    /// Parses a map of `#` (true) and `.` (false) characters
+    pub fn from_map<'a, I: IntoIterator<Item = Line<'a>>>(lines: I) -> Result<Self, ParseError> {
+        Self::parse(lines, |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): Position) -> &T {
+        self.get(row, col).expect("position out of bounds")
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, (row, col): Position) -> &mut T {
+        self.get_mut(row, col).expect("position out of bounds")
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::lines;
+    const SAMPLE: &str = "123\n456";
+
+    #[test]
+    fn test_parse() {
+        let grid = Grid::from_digits(lines(0, SAMPLE)).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 2)], 6);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get_signed(-1, 0), None);
+        assert_eq!(grid.to_string(), SAMPLE);
+
+        let map = Grid::from_map(lines(0, "#.\n.#")).unwrap();
+        assert_eq!(map.iter().filter(|&&lit| lit).count(), 2);
+        assert_eq!(
+            map.map(|&lit| if lit { '#' } else { '.' }).to_string(),
+            "#.\n.#"
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::from_digits(lines(0, SAMPLE)).unwrap();
+        let corner: Vec<_> = grid.neighbours4((0, 0)).collect();
+        assert_eq!(corner, vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours8((0, 1)).count(), 5);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = Grid::from_digits(lines(0, SAMPLE)).unwrap();
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        let rows: Vec<_> = grid.rows().map(|r| r.to_vec()).collect();
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let cols: Vec<Vec<_>> = grid.columns().map(|c| c.copied().collect()).collect();
+        assert_eq!(cols, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Grid::from_digits(lines(9, "123\n4x6")).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        let err = Grid::from_map(lines(20, "#.\n#")).unwrap_err();
+        assert_eq!(err.reason, "expected 2 cells, found 1");
+    }
+}
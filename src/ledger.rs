@@ -0,0 +1,150 @@
+use aoc_2021::Answer;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Known answers for the real puzzle inputs, stored as a small subset of TOML:
+///
+/// ```toml
+/// [day1]
+/// part1 = 1448
+///
+/// [day13]
+/// part2 = "CJCKBAPB"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ledger {
+    answers: BTreeMap<(u8, u8), String>,
+}
+
+fn parse_key(text: &str, prefix: &str) -> Option<u8> {
+    text.trim().strip_prefix(prefix)?.parse().ok()
+}
+
+fn is_number(text: &str) -> bool {
+    text.parse::<i64>().is_ok() || text.parse::<u64>().is_ok()
+}
+
+fn parse_value(text: &str) -> Option<String> {
+    let text = text.trim();
+    if let Some(quoted) = text.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.push(chars.next()?),
+                '"' => return chars.as_str().trim().is_empty().then_some(value),
+                c => value.push(c),
+            }
+        }
+        None
+    } else if is_number(text) {
+        Some(text.to_string())
+    } else {
+        None
+    }
+}
+
+impl Ledger {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut ledger = Self::default();
+        let mut day = None;
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let error = |reason: &str| format!("line {}: {}", i + 1, reason);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                day = Some(parse_key(header, "day").ok_or_else(|| error("expected `[day<N>]`"))?);
+                continue;
+            }
+            let day = day.ok_or_else(|| error("answer outside of a `[day<N>]` table"))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `part<N> = <answer>`"))?;
+            let part = parse_key(key, "part").ok_or_else(|| error("expected `part<N>`"))?;
+            let value = parse_value(value).ok_or_else(|| error("expected a number or string"))?;
+            ledger.answers.insert((day, part), value);
+        }
+        Ok(ledger)
+    }
+
+    /// Loads the ledger at `path`, a missing file being an empty ledger
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, part: u8, answer: &Answer) {
+        self.answers.insert((day, part), answer.to_string());
+    }
+
+    fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let mut current = None;
+        for (&(day, part), value) in &self.answers {
+            if current != Some(day) {
+                if current.is_some() {
+                    out.push('\n');
+                }
+                out += &format!("[day{}]\n", day);
+                current = Some(day);
+            }
+            let value = if is_number(value) {
+                value.clone()
+            } else {
+                format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+            };
+            out += &format!("part{} = {}\n", part, value);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const SAMPLE: &str = "# real answers
+[day1]
+part1 = 1448
+part2 = 1471
+
+[day13]
+part2 = \"CJCKBAPB\"
+";
+
+    #[test]
+    fn test_parse() {
+        let ledger = Ledger::parse(SAMPLE).unwrap();
+        assert_eq!(ledger.get(1, 2), Some("1471"));
+        assert_eq!(ledger.get(13, 2), Some("CJCKBAPB"));
+        assert_eq!(ledger.get(13, 1), None);
+        assert_eq!(
+            Ledger::parse("part1 = 3"),
+            Err("line 1: answer outside of a `[day<N>]` table".to_string())
+        );
+        assert!(Ledger::parse("[day2]\npart1 = abc").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut ledger = Ledger::parse(SAMPLE).unwrap();
+        ledger.insert(2, 1, &Answer::I64(-5));
+        ledger.insert(2, 2, &Answer::Text("say \"hi\"".to_string()));
+        ledger.insert(3, 1, &Answer::Text("42".to_string()));
+        assert_eq!(Ledger::parse(&ledger.to_toml()), Ok(ledger));
+    }
+}
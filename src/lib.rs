@@ -1,5 +1,18 @@
 use aoc_runner_derive::aoc_lib;
 
+pub mod export;
+pub mod grid;
+pub mod limits;
+pub mod parse;
+pub mod registry;
+pub mod simulation;
+pub mod snapshot;
+pub mod synth;
+pub mod viz;
+
+pub use parse::ParseError;
+pub use registry::{available, solve, solve_variant, Answer, Error};
+
 mod day1;
 mod day2;
 mod day3;
@@ -19,9 +32,12 @@ mod day15;
 mod day16;
 mod day17;
 mod day18;
-//mod day19;
+mod day19;
 mod day20;
 mod day21;
 mod day22;
+mod day23;
+mod day24;
+mod day25;
 
 aoc_lib! { year = 2021 }
@@ -0,0 +1,131 @@
+//! Limits on how long a solver may run. The loops that have no bound known in advance call `step`
+//! on every iteration, which counts against the step budget and the deadline of the solver that
+//! runs on the current thread. Going over either of them unwinds the solver without running the
+//! panic hook, and `run` turns the unwind back into an error.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// Steps between two looks at the clock, which costs more than counting
+const CLOCK_EVERY: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// Steps allowed across all the open-ended loops of a solver
+    pub budget: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exceeded {
+    Timeout(Duration),
+    Budget(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Active {
+    limits: Limits,
+    deadline: Option<Instant>,
+    steps: u64,
+}
+
+thread_local! {
+    static ACTIVE: Cell<Option<Active>> = const { Cell::new(None) };
+}
+
+/// Counts one step of an open-ended loop, a no-op outside of `run`
+pub fn step() {
+    ACTIVE.with(|active| {
+        let Some(mut current) = active.get() else {
+            return;
+        };
+        current.steps += 1;
+        active.set(Some(current));
+        if let Some(budget) = current.limits.budget.filter(|&b| current.steps > b) {
+            panic::resume_unwind(Box::new(Exceeded::Budget(budget)));
+        }
+        if current.steps.is_multiple_of(CLOCK_EVERY)
+            && current.deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            let timeout = current.limits.timeout.unwrap_or_default();
+            panic::resume_unwind(Box::new(Exceeded::Timeout(timeout)));
+        }
+    });
+}
+
+/// Runs `f` on the current thread, stopping it at the first `step` past the limits. Any other
+/// panic keeps unwinding.
+pub fn run<T, F: FnOnce() -> T>(limits: Limits, f: F) -> Result<T, Exceeded> {
+    let active = Active {
+        limits,
+        deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        steps: 0,
+    };
+    let previous = ACTIVE.replace(Some(active));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    ACTIVE.set(previous);
+    result.map_err(|payload| match payload.downcast::<Exceeded>() {
+        Ok(exceeded) => *exceeded,
+        Err(payload) => panic::resume_unwind(payload),
+    })
+}
+
+/// Message of a panic payload, which is a `&str` or a `String` for the usual `panic!` calls
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(steps: u64) -> u64 {
+        (0..steps).map(|_| step()).count() as u64
+    }
+
+    #[test]
+    fn test_budget() {
+        let limits = Limits {
+            budget: Some(100),
+            ..Limits::default()
+        };
+        assert_eq!(run(limits, || count(100)), Ok(100));
+        assert_eq!(run(limits, || count(101)), Err(Exceeded::Budget(100)));
+        // every run starts over, and steps outside of a run count against nothing
+        assert_eq!(run(limits, || count(50) + count(50)), Ok(100));
+        assert_eq!(count(1000), 1000);
+        assert_eq!(run(Limits::default(), || count(1000)), Ok(1000));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let timeout = Duration::from_millis(20);
+        let limits = Limits {
+            timeout: Some(timeout),
+            ..Limits::default()
+        };
+        let forever = || loop {
+            step();
+        };
+        assert_eq!(run(limits, forever), Err(Exceeded::Timeout(timeout)));
+    }
+
+    #[test]
+    fn test_panic() {
+        let result =
+            panic::catch_unwind(|| run(Limits::default(), || panic::resume_unwind(Box::new(7))));
+        assert_eq!(result.unwrap_err().downcast_ref::<i32>(), Some(&7));
+        let payload = panic::catch_unwind(|| panic!("day {}", 3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "day 3");
+        let payload = panic::catch_unwind(|| panic::resume_unwind(Box::new(7))).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "unknown panic");
+    }
+}
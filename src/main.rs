@@ -1,3 +1,470 @@
-use aoc_runner_derive::aoc_main;
+mod bench;
+mod cache;
+mod cli;
+mod ledger;
+mod pool;
+mod report;
 
-aoc_main! { lib = aoc_2021 }
+use aoc_2021::limits::Limits;
+use aoc_2021::registry::{self, Answer, Day, Input, Solver};
+use aoc_2021::{export, synth, viz};
+use cache::Cache;
+use cli::{Command, Options};
+use ledger::Ledger;
+use pool::Pool;
+use report::{Record, Reporter};
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Whether `day` has an input file in the input directory, stdin or a single file always count
+fn has_input(path: Option<&Path>, day: u8) -> bool {
+    match path {
+        Some(path) if path.is_dir() => path.join(format!("day{}.txt", day)).is_file(),
+        _ => true,
+    }
+}
+
+/// Reads the input of `day`: `path` is either the input file itself or a directory holding one
+/// `day<N>.txt` per day. Stdin is used without a path.
+fn read_input(path: Option<&Path>, day: u8) -> Result<String, String> {
+    let input = match path {
+        Some(path) if path.is_dir() => {
+            let file = path.join(format!("day{}.txt", day));
+            fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?
+        }
+        Some(path) if path != Path::new("-") => {
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?
+        }
+        _ => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("stdin: {}", e))?;
+            input
+        }
+    };
+    Ok(input.trim_end().to_string())
+}
+
+/// The selected parts that `day` actually has
+fn selected_parts(day: &Day, parts: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let available = day.parts();
+    let parts: Vec<u8> = match parts {
+        Some(parts) => parts
+            .iter()
+            .copied()
+            .filter(|p| available.contains(p))
+            .collect(),
+        None => available,
+    };
+    if parts.is_empty() {
+        return Err(format!("day {} has none of the selected parts", day.day));
+    }
+    Ok(parts)
+}
+
+/// Parses `input`, going through the cache when there is one
+fn parse_input(day: &Day, input: &str, cache: Option<&Cache>) -> Result<Input, String> {
+    match cache {
+        Some(cache) => cache.generate(day, input),
+        None => day.generate(input).map_err(|e| e.to_string()),
+    }
+}
+
+/// The default solver of every part, or all of its variants when cross-checking
+fn solvers<'a>(day: &'a Day, parts: &[u8], cross_check: bool) -> Vec<&'a Solver> {
+    let variants = if cross_check { usize::MAX } else { 1 };
+    parts
+        .iter()
+        .flat_map(|&part| day.variants(part).take(variants))
+        .collect()
+}
+
+/// Answer of a single solver run and the time it took
+struct Solved {
+    part: u8,
+    variant: &'static str,
+    answer: Result<Answer, registry::Error>,
+    elapsed: Duration,
+}
+
+/// Reports the answers of `day` and fails when the variants of a part disagree or a solver went
+/// over its limits. `generator` is the time it took to get the parsed input.
+fn report_day<W: Write>(
+    day: &Day,
+    parts: &[u8],
+    generator: Duration,
+    solved: &[Solved],
+    reporter: &mut Reporter<W>,
+) -> Result<(), String> {
+    reporter.generated(day.day, generator)?;
+    let mut answers = Vec::new();
+    for solved in solved {
+        let answer = solved.answer.as_ref().map_err(|e| e.to_string())?;
+        reporter.record(&Record {
+            day: day.day,
+            part: solved.part,
+            variant: solved.variant,
+            answer: answer.to_string(),
+            answer_type: answer.type_name(),
+            generator_ns: report::nanos(generator),
+            solver_ns: report::nanos(solved.elapsed),
+        })?;
+        answers.push((solved.part, solved.variant, answer));
+    }
+    for &part in parts {
+        let answers: Vec<_> = answers.iter().filter(|(p, ..)| *p == part).collect();
+        if answers.iter().any(|(.., answer)| *answer != answers[0].2) {
+            let answers: Vec<_> = answers
+                .iter()
+                .map(|(_, variant, answer)| format!("{} = {}", variant, answer))
+                .collect();
+            return Err(format!(
+                "variants of part {} disagree: {}",
+                part,
+                answers.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    passed: usize,
+    failed: usize,
+    missing: usize,
+}
+
+/// Checks the answers of the default solvers of `day` against the ledger, adding the missing ones
+/// when `record` is set. `solved` is `None` when the day has no input file.
+fn verify_day(
+    day: &Day,
+    parts: &[u8],
+    solved: Option<&[Solved]>,
+    ledger: &mut Ledger,
+    record: bool,
+    tally: &mut Tally,
+) -> Result<(), String> {
+    for &part in parts {
+        let label = format!("day {:>2} part {}", day.day, part);
+        let solved = match solved {
+            Some(solved) => solved,
+            None => {
+                println!("{}: missing input", label);
+                tally.missing += 1;
+                continue;
+            }
+        };
+        let answer = solved
+            .iter()
+            .find(|s| s.part == part)
+            .ok_or_else(|| format!("part {} was not solved", part))?
+            .answer
+            .as_ref()
+            .map_err(|e| e.to_string())?;
+        match ledger.get(day.day, part) {
+            Some(expected) if expected == answer.to_string() => {
+                println!("{}: pass", label);
+                tally.passed += 1;
+            }
+            Some(expected) => {
+                println!("{}: FAIL, expected {}, got {}", label, expected, answer);
+                tally.failed += 1;
+            }
+            None => {
+                let note = if record { ", recorded" } else { "" };
+                println!("{}: missing answer, got {}{}", label, answer, note);
+                tally.missing += 1;
+                if record {
+                    ledger.insert(day.day, part, answer);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a random input for `day` to `<output>/day<N>.txt`, or to stdout without a directory
+fn synth_day(day: u8, options: &Options) -> Result<(), String> {
+    let size = options.size.unwrap_or_else(|| synth::default_size(day));
+    let input = synth::generate(day, size, options.seed)
+        .ok_or_else(|| registry::Error::UnknownDay(day).to_string())?;
+    match &options.output {
+        Some(dir) => {
+            let file = dir.join(format!("day{}.txt", day));
+            fs::write(&file, input + "\n").map_err(|e| format!("{}: {}", file.display(), e))
+        }
+        None => {
+            println!("{}", input);
+            Ok(())
+        }
+    }
+}
+
+/// Plays the animation of `day` if it has one
+fn visualize_day(day: u8, parsed: &Input, options: &Options) -> Result<(), String> {
+    match viz::animate(day, parsed) {
+        Some(frames) => viz::play(frames, &mut io::stdout(), options.frame_delay)
+            .map_err(|e| format!("could not draw the animation: {}", e)),
+        None => {
+            eprintln!("day {} has no visualization", day);
+            Ok(())
+        }
+    }
+}
+
+/// Saves the pictures of `day` as `<dir>/day<N>-<name>.<extension>`
+fn export_day(day: u8, parsed: &Input, dir: &Path) -> Result<(), String> {
+    let images = match export::images(day, parsed) {
+        Some(images) => images,
+        None => {
+            eprintln!("day {} has no pictures to export", day);
+            return Ok(());
+        }
+    };
+    for (name, image) in images {
+        let file = dir.join(format!("day{}-{}.{}", day, name, image.extension()));
+        let error = |e: io::Error| format!("{}: {}", file.display(), e);
+        let mut out = BufWriter::new(fs::File::create(&file).map_err(error)?);
+        image
+            .write(&mut out)
+            .and_then(|_| out.flush())
+            .map_err(error)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    if let Err(err) = cli::check_days(&options) {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+
+    if let Err(err) = execute(&options) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// A selected day with its input, which is `None` when verifying a day without an input file
+struct Job {
+    day: &'static Day,
+    parts: Vec<u8>,
+    input: Option<String>,
+}
+
+/// Looks up `number` and reads its input
+fn prepare(number: u8, options: &Options) -> Result<Job, String> {
+    let day = registry::day(number).map_err(|e| e.to_string())?;
+    let parts = selected_parts(day, options.parts.as_deref())?;
+    let path = options.input.as_deref();
+    let input = match options.command {
+        Command::Verify if !has_input(path, number) => None,
+        _ => Some(read_input(path, number)?),
+    };
+    Ok(Job { day, parts, input })
+}
+
+/// Plays the animation and saves the pictures of `day` when asked to
+fn show_day(day: u8, parsed: &Input, options: &Options) -> Result<(), String> {
+    if options.visualize {
+        visualize_day(day, parsed, options)?;
+    }
+    if let Some(dir) = &options.export {
+        export_day(day, parsed, dir)?;
+    }
+    Ok(())
+}
+
+/// Runs the command on every selected day. Failing days are reported and skipped, the returned
+/// error only says that something failed.
+fn execute(options: &Options) -> Result<(), String> {
+    let baseline = match &options.baseline {
+        Some(path) => Some(bench::load_baseline(path)?),
+        None => None,
+    };
+    let mut results = bench::Baseline::new();
+    let mut ledger = match options.command {
+        Command::Verify => Ledger::load(&options.answers)?,
+        _ => Ledger::default(),
+    };
+    let mut tally = Tally::default();
+    let mut reporter = Reporter::new(options.format, io::stdout());
+    let cache = options.cache.clone().map(Cache::new);
+
+    let mut failed = 0;
+    let mut fail = |number: u8, err: String| {
+        eprintln!("error: day {}: {}", number, err);
+        failed += 1;
+    };
+    match options.command {
+        Command::Synth => {
+            for &number in &options.days {
+                if let Err(err) = synth_day(number, options) {
+                    fail(number, err);
+                }
+            }
+        }
+        // one day at a time, so that the timings don't get in each other's way
+        Command::Bench => {
+            for &number in &options.days {
+                let result = prepare(number, options).and_then(|job| {
+                    bench::bench_day(
+                        job.day,
+                        &job.parts,
+                        job.input.as_deref().unwrap_or_default(),
+                        cache.as_ref(),
+                        options.iterations,
+                        baseline.as_ref(),
+                        &mut results,
+                    )
+                });
+                if let Err(err) = result {
+                    fail(number, err);
+                }
+            }
+        }
+        Command::Run | Command::Verify => {
+            // stdin can only be read once, so the inputs are read up front on this thread
+            let jobs: Vec<_> = options
+                .days
+                .iter()
+                .map(|&number| prepare(number, options))
+                .collect();
+            let pool = Pool::new(options.jobs);
+            let parsed = pool.map(&jobs, |job| -> Result<_, String> {
+                let job = job.as_ref().map_err(Clone::clone)?;
+                let Some(input) = &job.input else {
+                    return Ok(None);
+                };
+                let start = Instant::now();
+                let parsed = parse_input(job.day, input, cache.as_ref())?;
+                Ok(Some((Arc::new(parsed), start.elapsed())))
+            });
+            let mut days: Vec<Result<_, String>> = jobs
+                .into_iter()
+                .zip(parsed)
+                .map(|(job, parsed)| {
+                    let parsed =
+                        parsed.map_err(|message| format!("generator panicked: {}", message));
+                    Ok((job?, parsed??))
+                })
+                .collect();
+
+            // animations take over the terminal, so they are played one day after the other
+            if options.command == Command::Run {
+                for day in &mut days {
+                    let result = match day {
+                        Ok((job, Some((parsed, _)))) => show_day(job.day.day, parsed, options),
+                        _ => Ok(()),
+                    };
+                    if let Err(err) = result {
+                        *day = Err(err);
+                    }
+                }
+            }
+
+            let cross_check = options.command == Command::Run && options.cross_check;
+            let tasks: Vec<(usize, &Solver)> = days
+                .iter()
+                .enumerate()
+                .filter_map(|(i, day)| match day {
+                    Ok((job, Some(_))) => Some((i, job)),
+                    _ => None,
+                })
+                .flat_map(|(i, job)| {
+                    solvers(job.day, &job.parts, cross_check)
+                        .into_iter()
+                        .map(move |solver| (i, solver))
+                })
+                .collect();
+            let limits = Limits {
+                timeout: options.timeout,
+                budget: options.budget,
+            };
+            let answers = pool.map(&tasks, |&(i, solver)| {
+                let Ok((_, Some((parsed, _)))) = &days[i] else {
+                    unreachable!("only days with a parsed input get solved");
+                };
+                let start = Instant::now();
+                let answer = solver.solve_within(parsed, limits);
+                Solved {
+                    part: solver.part,
+                    variant: solver.name,
+                    answer,
+                    elapsed: start.elapsed(),
+                }
+            });
+            let mut solved: Vec<Vec<Solved>> = days.iter().map(|_| Vec::new()).collect();
+            for (&(i, solver), answer) in tasks.iter().zip(answers) {
+                solved[i].push(answer.unwrap_or_else(|message| Solved {
+                    part: solver.part,
+                    variant: solver.name,
+                    answer: Err(registry::Error::Panicked {
+                        day: solver.day,
+                        part: solver.part,
+                        message,
+                    }),
+                    elapsed: Duration::ZERO,
+                }));
+            }
+
+            for ((&number, day), solved) in options.days.iter().zip(days).zip(solved) {
+                let result = day.and_then(|(job, parsed)| match options.command {
+                    Command::Run => {
+                        let generator = parsed.map(|(_, elapsed)| elapsed).unwrap_or_default();
+                        report_day(job.day, &job.parts, generator, &solved, &mut reporter)
+                    }
+                    _ => verify_day(
+                        job.day,
+                        &job.parts,
+                        parsed.map(|_| solved.as_slice()),
+                        &mut ledger,
+                        options.record,
+                        &mut tally,
+                    ),
+                });
+                if let Err(err) = result {
+                    fail(number, err);
+                }
+            }
+        }
+    }
+
+    if let Some(path) = &options.save_baseline {
+        bench::save_baseline(path, &results)?;
+    }
+    if options.command == Command::Verify {
+        println!(
+            "{} passed, {} failed, {} missing",
+            tally.passed, tally.failed, tally.missing
+        );
+        if options.record {
+            ledger.save(&options.answers)?;
+        }
+        if tally.failed > 0 {
+            return Err(format!("{} answer(s) did not match", tally.failed));
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} day(s) failed", n)),
+    }
+}
@@ -0,0 +1,150 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Error returned by the input generators, pointing at the offending spot of the puzzle input.
+/// Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new<S: Into<String>>(day: u8, line: usize, column: usize, reason: S) -> Self {
+        Self {
+            day,
+            line,
+            column,
+            reason: reason.into(),
+        }
+    }
+
+    /// Error for input that stops before the generator found everything it needed
+    pub fn eof<S: Into<String>>(day: u8, input: &str, reason: S) -> Self {
+        Self::new(day, input.lines().count() + 1, 1, reason)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} input, line {}, column {}: {}",
+            self.day, self.line, self.column, self.reason
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// A line of puzzle input that remembers where it came from. Every `part` passed to its helpers
+/// must be a slice of `text`, which is how the column of an error is found.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub day: u8,
+    pub number: usize,
+    pub text: &'a str,
+}
+
+pub fn lines(day: u8, input: &str) -> impl Iterator<Item = Line<'_>> {
+    input.lines().enumerate().map(move |(i, text)| Line {
+        day,
+        number: i + 1,
+        text,
+    })
+}
+
+impl<'a> Line<'a> {
+    pub fn column(&self, part: &str) -> usize {
+        let offset = (part.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        if offset <= self.text.len() {
+            offset + 1
+        } else {
+            1
+        }
+    }
+
+    pub fn error<S: Into<String>>(&self, reason: S) -> ParseError {
+        ParseError::new(self.day, self.number, 1, reason)
+    }
+
+    pub fn error_at<S: Into<String>>(&self, part: &str, reason: S) -> ParseError {
+        ParseError::new(self.day, self.number, self.column(part), reason)
+    }
+
+    pub fn parse<T>(&self, part: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let trimmed = part.trim();
+        trimmed
+            .parse()
+            .map_err(|e| self.error_at(trimmed, format!("invalid value `{}`: {}", trimmed, e)))
+    }
+
+    pub fn split_once(&self, part: &'a str, sep: &str) -> Result<(&'a str, &'a str), ParseError> {
+        part.split_once(sep)
+            .ok_or_else(|| self.error_at(part, format!("expected `{}` in `{}`", sep, part)))
+    }
+
+    pub fn strip_prefix(&self, part: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+        part.strip_prefix(prefix)
+            .ok_or_else(|| self.error_at(part, format!("expected `{}`", prefix)))
+    }
+
+    /// Pulls the next field out of `iter`, failing with a "missing" error at the end of the line
+    pub fn next<I: Iterator<Item = &'a str>>(
+        &self,
+        iter: &mut I,
+        what: &str,
+    ) -> Result<&'a str, ParseError> {
+        iter.next().ok_or_else(|| {
+            let end = &self.text[self.text.len()..];
+            self.error_at(end, format!("missing {}", what))
+        })
+    }
+
+    /// Parses a `sep` separated list of values
+    pub fn list<T>(&self, part: &'a str, sep: &str) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        part.split(sep).map(|n| self.parse(n)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_location() {
+        let input = "1,2\n3,x";
+        let mut iter = lines(7, input);
+        let first = iter.next().unwrap();
+        assert_eq!(first.list::<u32>(first.text, ","), Ok(vec![1, 2]));
+        let second = iter.next().unwrap();
+        let err = second.list::<u32>(second.text, ",").unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (7, 2, 3));
+        assert!(err
+            .to_string()
+            .starts_with("day 7 input, line 2, column 3:"));
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let line = lines(2, "forward").next().unwrap();
+        let mut parts = line.text.split(' ');
+        assert_eq!(line.next(&mut parts, "command"), Ok("forward"));
+        let err = line.next(&mut parts, "amount").unwrap_err();
+        assert_eq!((err.column, err.reason.as_str()), (8, "missing amount"));
+        let err = line.split_once(line.text, " ").unwrap_err();
+        assert_eq!(err.column, 1);
+        assert_eq!(ParseError::eof(2, "a\nb", "truncated").line, 3);
+    }
+}
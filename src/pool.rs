@@ -0,0 +1,115 @@
+use aoc_2021::limits;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Number of threads the machine can run at once
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Worker threads that take the next task off a shared list until none is left
+#[derive(Debug, Clone, Copy)]
+pub struct Pool {
+    jobs: usize,
+}
+
+impl Pool {
+    pub fn new(jobs: usize) -> Self {
+        Self { jobs: jobs.max(1) }
+    }
+
+    /// Runs `f` on every task and returns the results in the order of the tasks, whichever
+    /// thread finished them first. A task that panics only fails itself, with the message of the
+    /// panic as its error. A single job runs everything on the calling thread.
+    pub fn map<T, R, F>(&self, tasks: &[T], f: F) -> Vec<Result<R, String>>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> R + Sync,
+    {
+        let run = |task| {
+            panic::catch_unwind(AssertUnwindSafe(|| f(task)))
+                .map_err(|payload| limits::panic_message(payload.as_ref()))
+        };
+        if self.jobs == 1 {
+            return tasks.iter().map(run).collect();
+        }
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new((0..tasks.len()).map(|_| None).collect::<Vec<_>>());
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(tasks.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(task) = tasks.get(index) else {
+                        break;
+                    };
+                    let result = run(task);
+                    results.lock().unwrap()[index] = Some(result);
+                });
+            }
+        });
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("every task was run"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_map() {
+        let tasks: Vec<u64> = (0..50).collect();
+        // later tasks finish first, the results still come back in task order
+        let slow_square = |&n: &u64| {
+            thread::sleep(Duration::from_micros(50 - n));
+            n * n
+        };
+        let expected: Vec<_> = tasks.iter().map(|n| Ok(n * n)).collect();
+        assert_eq!(Pool::new(4).map(&tasks, slow_square), expected);
+        assert_eq!(Pool::new(1).map(&tasks, slow_square), expected);
+        assert_eq!(Pool::new(0).map(&tasks, slow_square), expected);
+        assert!(Pool::new(8).map(&[] as &[u64], slow_square).is_empty());
+    }
+
+    #[test]
+    fn test_threads() {
+        let tasks = [(); 20];
+        let ids: Vec<_> = Pool::new(2)
+            .map(&tasks, |_| {
+                thread::sleep(Duration::from_millis(5));
+                thread::current().id()
+            })
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let mut distinct = ids.clone();
+        distinct.sort_by_key(|id| format!("{:?}", id));
+        distinct.dedup();
+        assert_eq!(distinct.len(), 2);
+        assert!(!ids.contains(&thread::current().id()));
+    }
+
+    #[test]
+    fn test_panic() {
+        let tasks: Vec<u64> = (0..10).collect();
+        let halve = |&n: &u64| {
+            assert!(n % 2 == 0, "{} is odd", n);
+            n / 2
+        };
+        for jobs in [1, 3] {
+            let results = Pool::new(jobs).map(&tasks, halve);
+            assert_eq!(results[4], Ok(2));
+            assert_eq!(results[7], Err("7 is odd".to_string()));
+            assert_eq!(results.iter().filter(|r| r.is_err()).count(), 5);
+        }
+    }
+}
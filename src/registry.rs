@@ -0,0 +1,640 @@
+use crate::limits::{self, Exceeded, Limits};
+use crate::parse::ParseError;
+use crate::snapshot::Snapshot;
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+/// Parsed puzzle input as produced by a day's `input_generator`, which can be shared between the
+/// threads solving its parts
+pub type Input = Box<dyn Any + Send + Sync>;
+
+/// Common type for the values returned by the solvers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    U64(u64),
+    U32(u32),
+    Usize(usize),
+    I64(i64),
+    Text(String),
+}
+
+impl Answer {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Answer::U64(_) => "u64",
+            Answer::U32(_) => "u32",
+            Answer::Usize(_) => "usize",
+            Answer::I64(_) => "i64",
+            Answer::Text(_) => "String",
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::U64(val) => write!(f, "{}", val),
+            Answer::U32(val) => write!(f, "{}", val),
+            Answer::Usize(val) => write!(f, "{}", val),
+            Answer::I64(val) => write!(f, "{}", val),
+            Answer::Text(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(val: u64) -> Self {
+        Answer::U64(val)
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(val: u32) -> Self {
+        Answer::U32(val)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(val: usize) -> Self {
+        Answer::Usize(val)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(val: i64) -> Self {
+        Answer::I64(val)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(val: String) -> Self {
+        Answer::Text(val)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    UnknownDay(u8),
+    UnknownPart {
+        day: u8,
+        part: u8,
+    },
+    UnknownVariant {
+        day: u8,
+        part: u8,
+        name: String,
+    },
+    UnknownOutput {
+        day: u8,
+        name: String,
+    },
+    Timeout {
+        day: u8,
+        part: u8,
+        timeout: Duration,
+    },
+    BudgetExceeded {
+        day: u8,
+        part: u8,
+        budget: u64,
+    },
+    /// The solver found the input has no answer
+    Failed {
+        day: u8,
+        part: u8,
+        reason: String,
+    },
+    Panicked {
+        day: u8,
+        part: u8,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::UnknownDay(day) => write!(f, "day {} is not solved", day),
+            Error::UnknownPart { day, part } => write!(f, "day {} has no part {}", day, part),
+            Error::UnknownVariant { day, part, name } => {
+                write!(f, "day {} part {} has no variant `{}`", day, part, name)
+            }
+            Error::UnknownOutput { day, name } => {
+                write!(f, "day {} has no output `{}`", day, name)
+            }
+            Error::Timeout { day, part, timeout } => {
+                write!(f, "day {} part {} timed out after {:?}", day, part, timeout)
+            }
+            Error::BudgetExceeded { day, part, budget } => write!(
+                f,
+                "day {} part {} went over its budget of {} steps",
+                day, part, budget
+            ),
+            Error::Failed { day, part, reason } => {
+                write!(f, "day {} part {} failed: {}", day, part, reason)
+            }
+            Error::Panicked { day, part, message } => {
+                write!(f, "day {} part {} panicked: {}", day, part, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+/// What a solver returns, an answer or the reason its input has none
+pub trait Solution {
+    fn into_answer(self) -> Result<Answer, String>;
+}
+
+impl<A: Into<Answer>> Solution for A {
+    fn into_answer(self) -> Result<Answer, String> {
+        Ok(self.into())
+    }
+}
+
+impl<A: Into<Answer>, E: fmt::Display> Solution for Result<A, E> {
+    fn into_answer(self) -> Result<Answer, String> {
+        self.map(Into::into).map_err(|e| e.to_string())
+    }
+}
+
+pub struct Solver {
+    pub day: u8,
+    pub part: u8,
+    pub name: &'static str,
+    solve: fn(&Input) -> Result<Answer, String>,
+}
+
+impl Solver {
+    /// Runs the solver on the output of its day's generator
+    pub fn solve(&self, input: &Input) -> Result<Answer, Error> {
+        (self.solve)(input).map_err(|reason| Error::Failed {
+            day: self.day,
+            part: self.part,
+            reason,
+        })
+    }
+
+    /// Same as `solve` but fails once the solver goes over `limits`, or with the message of its
+    /// panic. With a timeout the solver runs on a thread of its own, which checks the same deadline
+    /// in its steps and stops by itself shortly after giving up on it. A solver with no step to
+    /// stop at is abandoned: it keeps running in the background and its answer is thrown away.
+    pub fn solve_within(
+        &'static self,
+        input: &Arc<Input>,
+        limits: Limits,
+    ) -> Result<Answer, Error> {
+        let error = |exceeded| match exceeded {
+            Exceeded::Timeout(timeout) => Error::Timeout {
+                day: self.day,
+                part: self.part,
+                timeout,
+            },
+            Exceeded::Budget(budget) => Error::BudgetExceeded {
+                day: self.day,
+                part: self.part,
+                budget,
+            },
+        };
+        let panicked = |payload: Box<dyn Any + Send>| Error::Panicked {
+            day: self.day,
+            part: self.part,
+            message: limits::panic_message(payload.as_ref()),
+        };
+        let Some(timeout) = limits.timeout else {
+            let solve = || limits::run(limits, || self.solve(input));
+            return panic::catch_unwind(AssertUnwindSafe(solve))
+                .map_err(panicked)?
+                .map_err(error)?;
+        };
+        let (sender, receiver) = mpsc::channel();
+        let input = Arc::clone(input);
+        let handle = thread::spawn(move || sender.send(limits::run(limits, || self.solve(&input))));
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result.map_err(error)?,
+            Err(RecvTimeoutError::Timeout) => Err(error(Exceeded::Timeout(timeout))),
+            // the sender was dropped without sending, which only happens when the solver panicked
+            Err(RecvTimeoutError::Disconnected) => match handle.join() {
+                Err(payload) => Err(panicked(payload)),
+                Ok(_) => unreachable!("solver thread ended without an answer"),
+            },
+        }
+    }
+}
+
+/// Something a day computes from its input besides the answers to its parts
+pub struct Output {
+    pub day: u8,
+    pub name: &'static str,
+    compute: fn(&Input) -> Answer,
+}
+
+impl Output {
+    pub fn compute(&self, input: &Input) -> Answer {
+        (self.compute)(input)
+    }
+}
+
+pub struct Day {
+    pub day: u8,
+    generate: fn(&str) -> Result<Input, ParseError>,
+    snapshot: fn(&Input) -> Vec<u8>,
+    restore: fn(&[u8]) -> Option<Input>,
+    pub solvers: Vec<Solver>,
+    pub outputs: Vec<Output>,
+}
+
+impl Day {
+    pub fn generate(&self, input: &str) -> Result<Input, ParseError> {
+        (self.generate)(input)
+    }
+
+    /// Binary form of an input parsed by `generate`
+    pub fn snapshot(&self, input: &Input) -> Vec<u8> {
+        (self.snapshot)(input)
+    }
+
+    /// Reads back the output of `snapshot`, `None` when the bytes are malformed
+    pub fn restore(&self, bytes: &[u8]) -> Option<Input> {
+        (self.restore)(bytes)
+    }
+
+    pub fn parts(&self) -> Vec<u8> {
+        let mut parts: Vec<_> = self.solvers.iter().map(|s| s.part).collect();
+        parts.dedup();
+        parts
+    }
+
+    pub fn variants(&self, part: u8) -> impl Iterator<Item = &Solver> {
+        self.solvers.iter().filter(move |s| s.part == part)
+    }
+
+    /// Looks up a solver by name, or the first one registered for the part when `name` is `None`
+    pub fn solver(&self, part: u8, name: Option<&str>) -> Result<&Solver, Error> {
+        let mut variants = self.variants(part).peekable();
+        if variants.peek().is_none() {
+            return Err(Error::UnknownPart {
+                day: self.day,
+                part,
+            });
+        }
+        match name {
+            None => Ok(variants.next().unwrap()),
+            Some(name) => variants
+                .find(|s| s.name == name)
+                .ok_or_else(|| Error::UnknownVariant {
+                    day: self.day,
+                    part,
+                    name: name.to_string(),
+                }),
+        }
+    }
+
+    pub fn output(&self, name: &str) -> Result<&Output, Error> {
+        self.outputs
+            .iter()
+            .find(|o| o.name == name)
+            .ok_or_else(|| Error::UnknownOutput {
+                day: self.day,
+                name: name.to_string(),
+            })
+    }
+}
+
+/// The concrete generator output behind `input`
+pub fn downcast<T: 'static>(input: &Input) -> &T {
+    input
+        .downcast_ref::<T>()
+        .expect("input was generated for another day")
+}
+
+/// Glue between the concrete generator output of a day and the type erased registry. The
+/// generator is only passed in so `T` can be inferred.
+fn run<T, G, S, A>(_generator: G, solve: S, input: &Input) -> Answer
+where
+    T: 'static,
+    G: Fn(&str) -> Result<T, ParseError>,
+    S: Fn(&T) -> A,
+    A: Into<Answer>,
+{
+    solve(downcast(input)).into()
+}
+
+/// Same as `run` for the solvers, which may fail
+fn attempt<T, G, S, A>(_generator: G, solve: S, input: &Input) -> Result<Answer, String>
+where
+    T: 'static,
+    G: Fn(&str) -> Result<T, ParseError>,
+    S: Fn(&T) -> A,
+    A: Solution,
+{
+    solve(downcast(input)).into_answer()
+}
+
+fn snapshot<T, G>(_generator: G, input: &Input) -> Vec<u8>
+where
+    T: Snapshot + Send + Sync + 'static,
+    G: Fn(&str) -> Result<T, ParseError>,
+{
+    downcast::<T>(input).to_bytes()
+}
+
+fn restore<T, G>(_generator: G, bytes: &[u8]) -> Option<Input>
+where
+    T: Snapshot + Send + Sync + 'static,
+    G: Fn(&str) -> Result<T, ParseError>,
+{
+    Some(Box::new(T::from_bytes(bytes)?))
+}
+
+/// Answer of a solver whose number may not fit in a `u64`, spelled out in decimal when it doesn't
+fn number(bits: crate::day3::Bits) -> Answer {
+    match bits.to_u64() {
+        Some(val) => Answer::U64(val),
+        None => Answer::Text(bits.to_string()),
+    }
+}
+
+/// Lists the solvers of every day. Alternative implementations of a part are registered next to
+/// the main one under the name of their `#[aoc]` attribute, which by convention is the main name
+/// followed by a short suffix (`d51_dense`). The first solver listed for a part is its default.
+/// A solver returning a type of its day's own is followed by `as` and the function that turns it
+/// into an answer. Extra outputs of a day follow in a second block, named after the day
+/// (`d1_longest_run`).
+macro_rules! registry {
+    ($($module:ident => $day:literal {
+        $($part:literal $name:literal => $solver:ident $(as $convert:ident)?),+ $(,)?
+    } $(+ { $($output:literal => $compute:ident),+ $(,)? })?),+ $(,)?) => {
+        fn build() -> Vec<Day> {
+            vec![$(
+                Day {
+                    day: $day,
+                    generate: |input| Ok(Box::new(crate::$module::input_generator(input)?) as Input),
+                    snapshot: |input| snapshot(crate::$module::input_generator, input),
+                    restore: |bytes| restore(crate::$module::input_generator, bytes),
+                    solvers: vec![$(
+                        Solver {
+                            day: $day,
+                            part: $part,
+                            name: $name,
+                            solve: |input| {
+                                attempt(
+                                    crate::$module::input_generator,
+                                    |i| {
+                                        let solution = crate::$module::$solver(i);
+                                        $(let solution = $convert(solution);)?
+                                        solution
+                                    },
+                                    input,
+                                )
+                            },
+                        },
+                    )+],
+                    outputs: vec![$($(
+                        Output {
+                            day: $day,
+                            name: $output,
+                            compute: |input| {
+                                run(crate::$module::input_generator, |i| crate::$module::$compute(i), input)
+                            },
+                        },
+                    )+)?],
+                },
+            )+]
+        }
+    };
+}
+
+registry! {
+    day1 => 1 { 1 "d11" => part1, 2 "d12" => part2 } + {
+        "d1_longest_run" => longest_increasing_run,
+        "d1_largest_drop" => largest_drop,
+        "d1_moving_average" => moving_average_output,
+        "d1_delta_histogram" => delta_histogram_output,
+    },
+    day2 => 2 { 1 "d21" => part1, 2 "d22" => part2 },
+    day3 => 3 { 1 "d31" => part1 as number, 2 "d32" => part2 as number },
+    day4 => 4 { 1 "d41" => part1, 2 "d42" => part2 },
+    day5 => 5 {
+        1 "d51" => part1,
+        1 "d51_dense" => part1_dense,
+        2 "d52" => part2,
+        2 "d52_dense" => part2_dense,
+    },
+    day6 => 6 { 1 "d61" => part1, 2 "d62" => part2 },
+    day7 => 7 {
+        1 "d71" => part1,
+        1 "d71_median" => part1_median,
+        2 "d72" => part2,
+        2 "d72_mean" => part2_mean,
+    },
+    day8 => 8 { 1 "d81" => part1, 2 "d82" => part2 },
+    day9 => 9 { 1 "d91" => part1, 2 "d92" => part2 },
+    day10 => 10 { 1 "d101" => part1, 2 "d102" => part2 },
+    day11 => 11 { 1 "d111" => part1, 2 "d112" => part2 },
+    day12 => 12 { 1 "d121" => part1, 2 "d122" => part2 },
+    day13 => 13 { 1 "d131" => part1, 2 "d132" => part2 },
+    day14 => 14 { 1 "day14_1" => part1, 2 "day14_2" => part2 },
+    day15 => 15 { 1 "day15_1" => part1, 2 "day15_2" => part2 },
+    day16 => 16 { 1 "day16_1" => part1, 2 "day16_2" => part2 },
+    day17 => 17 { 1 "day17_1" => part1, 2 "day17_2" => part2 },
+    day18 => 18 { 1 "day18_1" => part1, 2 "day18_2" => part2 },
+    day19 => 19 { 1 "day19_1" => part1, 2 "day19_2" => part2 },
+    day20 => 20 { 1 "day20_1" => part1, 2 "day20_2" => part2 },
+    day21 => 21 { 1 "day21_1" => part1, 2 "day21_e" => part2 },
+    day22 => 22 {
+        1 "day22_1" => part1,
+        1 "day22_1_voxels" => part1_voxels,
+        2 "day22_2" => part2,
+    },
+    day23 => 23 { 1 "day23_1" => part1, 2 "day23_2" => part2 },
+    day24 => 24 { 1 "day24_1" => part1, 2 "day24_2" => part2 },
+    day25 => 25 { 1 "day25_1" => part1 },
+}
+
+/// Every registered day in order
+pub fn days() -> &'static [Day] {
+    static DAYS: OnceLock<Vec<Day>> = OnceLock::new();
+    DAYS.get_or_init(build)
+}
+
+pub fn day(day: u8) -> Result<&'static Day, Error> {
+    days()
+        .iter()
+        .find(|d| d.day == day)
+        .ok_or(Error::UnknownDay(day))
+}
+
+/// Every registered `(day, part, variant)` combination
+pub fn available() -> Vec<(u8, u8, &'static str)> {
+    days()
+        .iter()
+        .flat_map(|d| d.solvers.iter().map(|s| (s.day, s.part, s.name)))
+        .collect()
+}
+
+/// Parses `input` and computes the extra output of the given day with that name
+pub fn output(day: u8, name: &str, input: &str) -> Result<Answer, Error> {
+    let entry = self::day(day)?;
+    let output = entry.output(name)?;
+    let parsed = entry.generate(input)?;
+    Ok(output.compute(&parsed))
+}
+
+/// Parses `input` and runs the default solver for the given day and part
+pub fn solve(day: u8, part: u8, input: &str) -> Result<Answer, Error> {
+    solve_variant(day, part, None, input)
+}
+
+/// Same as `solve` but picks the solver by variant name when `name` is given
+pub fn solve_variant(day: u8, part: u8, name: Option<&str>, input: &str) -> Result<Answer, Error> {
+    let entry = self::day(day)?;
+    let solver = entry.solver(part, name)?;
+    let parsed = entry.generate(input)?;
+    solver.solve(&parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        assert_eq!(solve(1, 1, input), Ok(Answer::I64(7)));
+        assert_eq!(solve_variant(1, 1, Some("d11"), input), Ok(Answer::I64(7)));
+        assert_eq!(solve(16, 2, "D2FE28"), Ok(Answer::U64(2021)));
+        assert_eq!(
+            solve_variant(7, 2, Some("d72_mean"), "1,5,6"),
+            Ok(Answer::I64(10))
+        );
+        assert_eq!(solve(3, 2, "100\n110\n111"), Ok(Answer::U64(28)));
+        let wide = format!("{0}\n{0}", "1".repeat(65));
+        let answer = "1361129467683753853779711453432234639361".to_string();
+        assert_eq!(solve(3, 2, &wide), Ok(Answer::Text(answer)));
+        assert_eq!(
+            solve(19, 1, "--- scanner 0 ---\n1,2,3"),
+            Ok(Answer::Usize(1))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(solve(26, 1, ""), Err(Error::UnknownDay(26)));
+        assert_eq!(
+            solve(25, 2, ""),
+            Err(Error::UnknownPart { day: 25, part: 2 })
+        );
+        let err = solve_variant(1, 1, Some("fast"), "1").unwrap_err();
+        assert_eq!(err.to_string(), "day 1 part 1 has no variant `fast`");
+        let err = solve(2, 2, "down 1\nup 2\nforward 3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 2 part 2 failed: command 3 surfaces the submarine at depth -3"
+        );
+        let err = solve(24, 1, "inp w\nadd z w").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 24 part 1 failed: program does not look like MONAD"
+        );
+        match solve(1, 1, "1\nx") {
+            Err(Error::Parse(err)) => assert_eq!((err.day, err.line), (1, 2)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_outputs() {
+        let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        assert_eq!(output(1, "d1_longest_run", input), Ok(Answer::Usize(4)));
+        assert_eq!(output(1, "d1_largest_drop", input), Ok(Answer::I64(10)));
+        let averages = output(1, "d1_moving_average", input).unwrap();
+        assert!(averages.to_string().starts_with("202.33,206.00,"));
+        let err = output(1, "d11", input).unwrap_err();
+        assert_eq!(err.to_string(), "day 1 has no output `d11`");
+        assert!(day(2).unwrap().outputs.is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let graph = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end";
+        let day = day(12).unwrap();
+        let input = Arc::new(day.generate(graph).unwrap());
+        let solver = day.solver(2, None).unwrap();
+        let budget = |budget| Limits {
+            budget: Some(budget),
+            ..Limits::default()
+        };
+        assert_eq!(
+            solver.solve_within(&input, budget(1000)),
+            Ok(Answer::U64(36))
+        );
+        let err = solver.solve_within(&input, budget(10)).unwrap_err();
+        assert_eq!(
+            err,
+            Error::BudgetExceeded {
+                day: 12,
+                part: 2,
+                budget: 10
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "day 12 part 2 went over its budget of 10 steps"
+        );
+        let timeout = Limits {
+            timeout: Some(Duration::from_secs(60)),
+            ..budget(10)
+        };
+        assert_eq!(solver.solve_within(&input, timeout), Err(err));
+    }
+
+    #[test]
+    fn test_panic() {
+        // no registered solver panics on bad input anymore
+        static SOLVER: Solver = Solver {
+            day: 1,
+            part: 1,
+            name: "d11_panic",
+            solve: |input| panic!("cannot solve {:?}", downcast::<u8>(input)),
+        };
+        let input: Arc<Input> = Arc::new(Box::new(7_u8));
+        let solver = &SOLVER;
+        let timeout = Limits {
+            timeout: Some(Duration::from_secs(60)),
+            ..Limits::default()
+        };
+        for limits in [Limits::default(), timeout] {
+            match solver.solve_within(&input, limits) {
+                Err(Error::Panicked { day, part, message }) => {
+                    assert_eq!((day, part), (1, 1));
+                    assert_eq!(message, "cannot solve 7");
+                }
+                other => panic!("expected a panic, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_available() {
+        let available = available();
+        assert_eq!(available.len(), 54);
+        assert!(available.contains(&(13, 2, "d132")));
+        assert!(available.contains(&(5, 2, "d52_dense")));
+        assert_eq!(day(25).unwrap().parts(), vec![1]);
+    }
+}
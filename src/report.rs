@@ -0,0 +1,217 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    /// One JSON object per line
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Result of one part, the generator time being shared by every part of the day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub answer: String,
+    pub answer_type: &'static str,
+    pub generator_ns: u64,
+    pub solver_ns: u64,
+}
+
+pub fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+const CSV_HEADER: &str = "day,part,variant,answer,answer_type,generator_ns,solver_ns";
+
+/// `value` as a JSON string literal
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `value` as a CSV field, quoted only when it holds a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Record {
+    fn json(&self) -> String {
+        format!(
+            "{{\"day\":{},\"part\":{},\"variant\":{},\"answer\":{},\"answer_type\":{},\
+             \"generator_ns\":{},\"solver_ns\":{}}}",
+            self.day,
+            self.part,
+            json_string(self.variant),
+            json_string(&self.answer),
+            json_string(self.answer_type),
+            self.generator_ns,
+            self.solver_ns
+        )
+    }
+
+    fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.day,
+            self.part,
+            csv_field(self.variant),
+            csv_field(&self.answer),
+            csv_field(self.answer_type),
+            self.generator_ns,
+            self.solver_ns
+        )
+    }
+}
+
+pub struct Reporter<W: Write> {
+    format: Format,
+    out: W,
+    header_written: bool,
+}
+
+impl<W: Write> Reporter<W> {
+    pub fn new(format: Format, out: W) -> Self {
+        Self {
+            format,
+            out,
+            header_written: false,
+        }
+    }
+
+    fn io_error(e: impl std::fmt::Display) -> String {
+        format!("could not write results: {}", e)
+    }
+
+    /// Called once the generator of a day ran, before any of its records
+    pub fn generated(&mut self, day: u8, elapsed: Duration) -> Result<(), String> {
+        if self.format == Format::Text {
+            writeln!(self.out, "Day {}\n  generator: {:?}", day, elapsed)
+                .map_err(Self::io_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, record: &Record) -> Result<(), String> {
+        match self.format {
+            Format::Text => writeln!(
+                self.out,
+                "  Part {} ({}): {}  [solver: {:?}]",
+                record.part,
+                record.variant,
+                record.answer,
+                Duration::from_nanos(record.solver_ns)
+            )
+            .map_err(Self::io_error),
+            Format::Json => writeln!(self.out, "{}", record.json()).map_err(Self::io_error),
+            Format::Csv => {
+                if !self.header_written {
+                    writeln!(self.out, "{}", CSV_HEADER).map_err(Self::io_error)?;
+                    self.header_written = true;
+                }
+                writeln!(self.out, "{}", record.csv()).map_err(Self::io_error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 13,
+                part: 1,
+                variant: "d131",
+                answer: "17".to_string(),
+                answer_type: "u64",
+                generator_ns: 1500,
+                solver_ns: 200,
+            },
+            Record {
+                day: 13,
+                part: 2,
+                variant: "d132",
+                answer: "CJ,\"K\"".to_string(),
+                answer_type: "String",
+                generator_ns: 1500,
+                solver_ns: 300,
+            },
+        ]
+    }
+
+    fn render(format: Format) -> String {
+        let mut out = Vec::new();
+        let mut reporter = Reporter::new(format, &mut out);
+        reporter.generated(13, Duration::from_nanos(1500)).unwrap();
+        for record in records() {
+            reporter.record(&record).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json() {
+        let out = render(Format::Json);
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "{\"day\":13,\"part\":1,\"variant\":\"d131\",\"answer\":\"17\",\"answer_type\":\"u64\",\
+             \"generator_ns\":1500,\"solver_ns\":200}"
+        );
+        assert!(lines[1].contains("\"answer\":\"CJ,\\\"K\\\"\","));
+        assert_eq!(json_string("a\\b\n\u{1}"), "\"a\\\\b\\n\\u0001\"");
+    }
+
+    #[test]
+    fn test_csv() {
+        let expected = "day,part,variant,answer,answer_type,generator_ns,solver_ns
+13,1,d131,17,u64,1500,200
+13,2,d132,\"CJ,\"\"K\"\"\",String,1500,300
+";
+        assert_eq!(render(Format::Csv), expected);
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_text() {
+        let out = render(Format::Text);
+        assert!(out.starts_with("Day 13\n  generator: 1.5µs\n  Part 1 (d131): 17"));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::limits;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Steps at which a simulation first entered the cycle it repeats, and the cycle length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// A state that evolves one step at a time
+pub trait Simulation {
+    fn step(&mut self);
+
+    fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Steps until `done` holds, returning the number of steps taken. Every step counts against
+    /// the limits of the running solver, since `done` may never hold.
+    fn run_until<P: FnMut(&Self) -> bool>(&mut self, mut done: P) -> usize
+    where
+        Self: Sized,
+    {
+        let mut steps = 0;
+        while !done(self) {
+            limits::step();
+            self.step();
+            steps += 1;
+        }
+        steps
+    }
+}
+
+/// A simulation whose running total keeps growing, while the rest of its state, the key, comes back
+/// around after a while. The key has to decide every later step, and the total has to grow by the
+/// same amount every time the key goes through its cycle.
+pub trait Periodic: Simulation {
+    type Key: Hash + Eq;
+
+    fn key(&self) -> Self::Key;
+
+    fn total(&self) -> u64;
+
+    fn add_total(&mut self, amount: u64);
+
+    /// Same as `run` but remembers every key it went through, and once one repeats skips over the
+    /// whole cycles left by adding what the total gained over one of them
+    fn fast_forward(&mut self, steps: usize) -> Option<Cycle>
+    where
+        Self: Sized,
+    {
+        let mut seen = HashMap::new();
+        for done in 0..steps {
+            let key = self.key();
+            if let Some(&(start, total)) = seen.get(&key) {
+                let (length, left) = (done - start, steps - done);
+                let gained = self.total() - total;
+                self.add_total(gained * (left / length) as u64);
+                self.run(left % length);
+                return Some(Cycle { start, length });
+            }
+            seen.insert(key, (done, self.total()));
+            self.step();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `x * x + 1 (mod 10)`, which from 3 enters the cycle 0, 1, 2, 5, 6, 7 after one step,
+    /// and adds up the values it goes through
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Square {
+        x: u64,
+        sum: u64,
+    }
+
+    impl Square {
+        fn new(x: u64) -> Self {
+            Self { x, sum: 0 }
+        }
+    }
+
+    impl Simulation for Square {
+        fn step(&mut self) {
+            self.x = (self.x * self.x + 1) % 10;
+            self.sum += self.x;
+        }
+    }
+
+    impl Periodic for Square {
+        type Key = u64;
+
+        fn key(&self) -> u64 {
+            self.x
+        }
+
+        fn total(&self) -> u64 {
+            self.sum
+        }
+
+        fn add_total(&mut self, amount: u64) {
+            self.sum += amount;
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut square = Square::new(0);
+        square.run(3);
+        assert_eq!(square.x, 5);
+        assert_eq!(square.run_until(|s| s.x == 0), 3);
+        assert_eq!(Square::new(0).run_until(|s| s.x == 0), 0);
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        for steps in [0, 4, 7, 50, 1001] {
+            let (mut fast, mut slow) = (Square::new(3), Square::new(3));
+            fast.fast_forward(steps);
+            slow.run(steps);
+            assert_eq!(fast, slow, "{} steps", steps);
+        }
+
+        // the first step goes to 0, then every cycle adds 1 + 2 + 5 + 6 + 7 + 0
+        let steps = 1_000_000_000_003;
+        let mut square = Square::new(3);
+        let cycle = square.fast_forward(steps);
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                start: 1,
+                length: 6
+            })
+        );
+        assert_eq!(
+            square,
+            Square {
+                x: 0,
+                sum: 21 * (steps as u64 - 1) / 6
+            }
+        );
+
+        let mut square = Square::new(3);
+        assert_eq!(square.fast_forward(4), None);
+        assert_eq!(square.x, 5);
+    }
+}
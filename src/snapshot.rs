@@ -0,0 +1,317 @@
+//! Compact binary form of the parsed inputs, so a cache can hand a day its input without parsing
+//! the text again. Integers are stored as LEB128 varints, signed ones zigzag encoded first, and
+//! collections as their length followed by their items. Hash maps and sets are written in sorted
+//! order so the same input always gives the same bytes.
+
+use crate::grid::Grid;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// A parsed input that can be written to bytes and read back
+pub trait Snapshot: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Reads a value from the start of `input` and advances past it, `None` on malformed bytes
+    fn decode(input: &mut &[u8]) -> Option<Self>;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Reads a value that has to take up all of `bytes`
+    fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let value = Self::decode(&mut bytes)?;
+        bytes.is_empty().then_some(value)
+    }
+}
+
+fn encode_varint(mut val: u64, out: &mut Vec<u8>) {
+    while val >= 0x80 {
+        out.push(val as u8 | 0x80);
+        val >>= 7;
+    }
+    out.push(val as u8);
+}
+
+fn decode_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut val = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        let bits = (byte & 0x7f) as u64;
+        if bits << shift >> shift != bits {
+            return None;
+        }
+        val |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(val);
+        }
+    }
+    None
+}
+
+/// Reads a collection length, which can't be more than the bytes left since every item takes at
+/// least one of them. Keeps garbage from allocating huge vectors.
+fn decode_len(input: &mut &[u8]) -> Option<usize> {
+    let len = usize::decode(input)?;
+    (len <= input.len()).then_some(len)
+}
+
+impl Snapshot for u8 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        Some(byte)
+    }
+}
+
+macro_rules! unsigned {
+    ($($ty:ty),+) => {$(
+        impl Snapshot for $ty {
+            fn encode(&self, out: &mut Vec<u8>) {
+                encode_varint(*self as u64, out);
+            }
+
+            fn decode(input: &mut &[u8]) -> Option<Self> {
+                decode_varint(input)?.try_into().ok()
+            }
+        }
+    )+};
+}
+
+unsigned!(u16, u32, u64, usize);
+
+impl Snapshot for i64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_varint(((self << 1) ^ (self >> 63)) as u64, out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let val = decode_varint(input)?;
+        Some((val >> 1) as i64 ^ -((val & 1) as i64))
+    }
+}
+
+impl Snapshot for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Snapshot for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u32).encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        char::from_u32(u32::decode(input)?)
+    }
+}
+
+impl Snapshot for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = decode_len(input)?;
+        let (bytes, rest) = input.split_at(len);
+        *input = rest;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl<T: Snapshot> Snapshot for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = decode_len(input)?;
+        (0..len).map(|_| T::decode(input)).collect()
+    }
+}
+
+impl<T: Snapshot, const N: usize> Snapshot for [T; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        for item in self {
+            item.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let items: Vec<T> = (0..N).map(|_| T::decode(input)).collect::<Option<_>>()?;
+        items.try_into().ok()
+    }
+}
+
+impl<T: Snapshot> Snapshot for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.is_some().encode(out);
+        if let Some(val) = self {
+            val.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match bool::decode(input)? {
+            true => Some(Some(T::decode(input)?)),
+            false => Some(None),
+        }
+    }
+}
+
+macro_rules! tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Snapshot),+> Snapshot for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, out: &mut Vec<u8>) {
+                let ($($name,)+) = self;
+                $($name.encode(out);)+
+            }
+
+            fn decode(input: &mut &[u8]) -> Option<Self> {
+                Some(($($name::decode(input)?,)+))
+            }
+        }
+    };
+}
+
+tuple!(A, B);
+tuple!(A, B, C);
+
+/// Writes already encoded items in byte order, which doesn't depend on the hasher
+fn encode_sorted<I: Iterator<Item = Vec<u8>>>(items: I, out: &mut Vec<u8>) {
+    let mut items: Vec<_> = items.collect();
+    items.sort_unstable();
+    items.len().encode(out);
+    for item in items {
+        out.extend(item);
+    }
+}
+
+impl<K: Snapshot + Eq + Hash, V: Snapshot> Snapshot for HashMap<K, V> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_sorted(
+            self.iter().map(|(key, val)| {
+                let mut item = key.to_bytes();
+                val.encode(&mut item);
+                item
+            }),
+            out,
+        );
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = decode_len(input)?;
+        (0..len)
+            .map(|_| Some((K::decode(input)?, V::decode(input)?)))
+            .collect()
+    }
+}
+
+impl<T: Snapshot + Eq + Hash> Snapshot for HashSet<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_sorted(self.iter().map(T::to_bytes), out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = decode_len(input)?;
+        (0..len).map(|_| T::decode(input)).collect()
+    }
+}
+
+impl<T: Snapshot> Snapshot for Grid<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.width().encode(out);
+        self.height().encode(out);
+        for cell in self.iter() {
+            cell.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (width, height) = (usize::decode(input)?, usize::decode(input)?);
+        let len = width.checked_mul(height)?;
+        if len > input.len() {
+            return None;
+        }
+        let cells = (0..len).map(|_| T::decode(input)).collect::<Option<_>>()?;
+        Some(Grid::new(width, height, cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry, synth};
+
+    fn round_trip<T: Snapshot + PartialEq + std::fmt::Debug>(val: T) {
+        assert_eq!(T::from_bytes(&val.to_bytes()), Some(val));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(0_u64);
+        round_trip(u64::MAX);
+        round_trip(300_u16);
+        round_trip(i64::MIN);
+        round_trip(-1_i64);
+        round_trip(vec![('é', true), ('x', false)]);
+        round_trip([Some("a".to_string()), None]);
+        round_trip(HashMap::from([([1_u8, 2], 3_u32), ([4, 5], 6)]));
+        round_trip(Grid::new(2, 1, vec![-5_i64, 5]));
+        assert_eq!(127_u64.to_bytes(), [127]);
+        assert_eq!(128_u64.to_bytes(), [0x80, 1]);
+        assert_eq!((-1_i64).to_bytes(), [1]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(u64::from_bytes(&[0x80]), None);
+        assert_eq!(u64::from_bytes(&[0xff; 11]), None);
+        assert_eq!(u16::from_bytes(&70_000_u32.to_bytes()), None);
+        assert_eq!(bool::from_bytes(&[2]), None);
+        assert_eq!(Vec::<u8>::from_bytes(&[5, 1]), None);
+        assert_eq!(u8::from_bytes(&[1, 2]), None);
+        assert_eq!(Grid::<u8>::from_bytes(&[2, 2, 1, 1, 1]), None);
+        let set = HashSet::from([3_u32, 1, 2]);
+        assert_eq!(set.to_bytes(), [3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_days() {
+        for day in registry::days() {
+            let input = synth::generate(day.day, 5, 1).unwrap();
+            let parsed = day.generate(&input).unwrap();
+            let bytes = day.snapshot(&parsed);
+            let restored = day
+                .restore(&bytes)
+                .expect("snapshot could not be read back");
+            assert_eq!(day.snapshot(&restored), bytes, "day {}", day.day);
+            for solver in &day.solvers {
+                let (expected, actual) = (solver.solve(&parsed), solver.solve(&restored));
+                assert_eq!(actual, expected, "day {} {}", day.day, solver.name);
+            }
+            assert!(day.restore(&bytes[..bytes.len() - 1]).is_none());
+        }
+    }
+}
@@ -0,0 +1,764 @@
+//! Random puzzle inputs for stress testing and benchmarking the solvers. Every input is valid for
+//! its day and fully determined by the seed. `size` scales the input: it is the number of lines or
+//! items for list inputs, the side of the map for grid inputs, the number of caves for day 12 and
+//! of scanners for day 19, while days 17, 21, 23 and 24 have a fixed shape and ignore it.
+
+use crate::{day11, day13, day23, day25};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// SplitMix64, which is plenty for test inputs and gives the same inputs on every platform
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let span = (range.end() - range.start()) as u64 + 1;
+        range.start() + (self.next_u64() % span) as i64
+    }
+
+    /// True `num` times out of `den`
+    pub fn chance(&mut self, num: usize, den: usize) -> bool {
+        self.below(den) < num
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Size of the real puzzle inputs
+pub fn default_size(day: u8) -> usize {
+    match day {
+        1 => 2000,
+        2 | 3 | 7 => 1000,
+        4 | 9 | 10 | 15 | 18 | 20 => 100,
+        5 => 500,
+        6 => 300,
+        8 => 200,
+        11 | 12 => 10,
+        13 => 8,
+        14 => 20,
+        16 => 300,
+        19 => 30,
+        22 => 420,
+        25 => 137,
+        _ => 1,
+    }
+}
+
+/// Random input for `day`, or `None` for a day outside of the calendar
+pub fn generate(day: u8, size: usize, seed: u64) -> Option<String> {
+    // every day draws from its own stream so the same seed gives unrelated inputs
+    let rng = &mut Rng::new(seed ^ (day as u64).wrapping_mul(0xa076_1d64_78bd_642f));
+    let size = size.max(1);
+    let input = match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size),
+        3 => day3(rng, size),
+        4 => day4(rng, size),
+        5 => day5(rng, size),
+        6 => day6(rng, size),
+        7 => day7(rng, size),
+        8 => day8(rng, size),
+        9 => day9(rng, size),
+        10 => day10(rng, size),
+        11 => day11(rng, size),
+        12 => day12(rng, size),
+        13 => day13(rng, size),
+        14 => day14(rng, size),
+        15 => day15(rng, size),
+        16 => day16(rng, size),
+        17 => day17(rng),
+        18 => day18(rng, size),
+        19 => day19(rng, size),
+        20 => day20(rng, size),
+        21 => day21(rng),
+        22 => day22(rng, size),
+        23 => day23(rng),
+        24 => day24(rng),
+        25 => day25(rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+fn join<I: IntoIterator<Item = String>>(items: I, separator: &str) -> String {
+    items.into_iter().collect::<Vec<_>>().join(separator)
+}
+
+fn grid<F: FnMut(&mut Rng) -> char>(
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+    mut cell: F,
+) -> String {
+    join(
+        (0..height).map(|_| (0..width).map(|_| cell(rng)).collect()),
+        "\n",
+    )
+}
+
+fn digit(rng: &mut Rng, digits: RangeInclusive<i64>) -> char {
+    (b'0' + rng.range(digits) as u8) as char
+}
+
+fn day1(rng: &mut Rng, size: usize) -> String {
+    let mut depth = rng.range(100..=200);
+    join(
+        (0..size).map(|_| {
+            depth = (depth + rng.range(-10..=30)).max(0);
+            depth.to_string()
+        }),
+        "\n",
+    )
+}
+
+/// The submarine never tries to rise above the surface
+fn day2(rng: &mut Rng, size: usize) -> String {
+    let mut depth = 0;
+    join(
+        (0..size).map(|_| {
+            let amount = rng.range(1..=9);
+            let action = match rng.below(20) {
+                0..=7 => "forward",
+                8..=14 => "down",
+                _ if depth >= amount => "up",
+                _ => "down",
+            };
+            match action {
+                "down" => depth += amount,
+                "up" => depth -= amount,
+                _ => {}
+            }
+            format!("{} {}", action, amount)
+        }),
+        "\n",
+    )
+}
+
+/// Distinct readings, so the rating filters always end on a single one
+fn day3(rng: &mut Rng, size: usize) -> String {
+    let mut readings: Vec<usize> = (0..1 << 12).collect();
+    rng.shuffle(&mut readings);
+    join(
+        readings.iter().take(size).map(|r| format!("{:012b}", r)),
+        "\n",
+    )
+}
+
+/// Every number gets drawn, so every card wins eventually
+fn day4(rng: &mut Rng, size: usize) -> String {
+    let mut numbers: Vec<usize> = (0..100).collect();
+    rng.shuffle(&mut numbers);
+    let mut out = join(numbers.iter().map(|n| n.to_string()), ",");
+    for _ in 0..size {
+        rng.shuffle(&mut numbers);
+        out.push('\n');
+        for row in numbers[..25].chunks(5) {
+            out.push('\n');
+            out += &join(row.iter().map(|n| format!("{:>2}", n)), " ");
+        }
+    }
+    out
+}
+
+/// Horizontal, vertical and 45 degree lines
+fn day5(rng: &mut Rng, size: usize) -> String {
+    const MAX: i64 = 999;
+    join(
+        (0..size).map(|_| {
+            let (x, y) = (rng.range(0..=MAX), rng.range(0..=MAX));
+            let (x2, y2) = match rng.below(3) {
+                0 => (rng.range(0..=MAX), y),
+                1 => (x, rng.range(0..=MAX)),
+                _ => {
+                    let (dx, dy) = (*rng.pick(&[-1, 1]), *rng.pick(&[-1, 1]));
+                    let room = |p: i64, d: i64| if d > 0 { MAX - p } else { p };
+                    let len = rng.range(0..=room(x, dx).min(room(y, dy)).min(300));
+                    (x + dx * len, y + dy * len)
+                }
+            };
+            format!("{},{} -> {},{}", x, y, x2, y2)
+        }),
+        "\n",
+    )
+}
+
+fn day6(rng: &mut Rng, size: usize) -> String {
+    join((0..size).map(|_| rng.range(1..=5).to_string()), ",")
+}
+
+fn day7(rng: &mut Rng, size: usize) -> String {
+    let max = (size as i64 * 2).max(10);
+    join((0..size).map(|_| rng.range(0..=max).to_string()), ",")
+}
+
+const SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+/// Lights `digit` through the scrambled `wiring`, listing the wires in random order
+fn wire(rng: &mut Rng, wiring: &[char], digit: usize) -> String {
+    let mut wires: Vec<char> = SEGMENTS[digit]
+        .chars()
+        .map(|c| wiring[(c as u8 - b'a') as usize])
+        .collect();
+    rng.shuffle(&mut wires);
+    wires.into_iter().collect()
+}
+
+/// Every display gets its own wiring
+fn day8(rng: &mut Rng, size: usize) -> String {
+    join(
+        (0..size).map(|_| {
+            let mut wiring: Vec<char> = ('a'..='g').collect();
+            rng.shuffle(&mut wiring);
+            let mut patterns: Vec<_> = (0..10).map(|d| wire(rng, &wiring, d)).collect();
+            rng.shuffle(&mut patterns);
+            let output: Vec<_> = (0..4)
+                .map(|_| {
+                    let d = rng.below(10);
+                    wire(rng, &wiring, d)
+                })
+                .collect();
+            format!("{} | {}", patterns.join(" "), output.join(" "))
+        }),
+        "\n",
+    )
+}
+
+/// Plants three low points so there are always three basins to multiply
+fn day9(rng: &mut Rng, size: usize) -> String {
+    let side = size.max(5);
+    let mut cells: Vec<Vec<u8>> = (0..side)
+        .map(|_| {
+            (0..side)
+                .map(|_| {
+                    if rng.chance(1, 4) {
+                        9
+                    } else {
+                        rng.below(9) as u8
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    for (row, col) in [(1, 1), (1, side - 2), (side - 2, 1)] {
+        for (r, c) in [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ] {
+            cells[r][c] = cells[r][c].max(1);
+        }
+        cells[row][col] = 0;
+    }
+    join(
+        cells
+            .iter()
+            .map(|row| row.iter().map(|&h| (b'0' + h) as char).collect()),
+        "\n",
+    )
+}
+
+/// An odd number of incomplete lines for the median, the rest being complete or corrupted
+fn day10(rng: &mut Rng, size: usize) -> String {
+    const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+    let mut incomplete = 0;
+    let mut lines: Vec<String> = (0..size)
+        .map(|i| {
+            let mut line = String::new();
+            let mut open = Vec::new();
+            for _ in 0..rng.range(4..=30) {
+                if open.is_empty() || rng.chance(3, 5) {
+                    let &(o, c) = rng.pick(&PAIRS);
+                    line.push(o);
+                    open.push(c);
+                } else {
+                    line.push(open.pop().unwrap());
+                }
+            }
+            let last = i + 1 == size;
+            if open.is_empty() || (last && incomplete % 2 == 0) {
+                // keep at least one chunk open
+                let &(o, c) = rng.pick(&PAIRS);
+                line.push(o);
+                open.push(c);
+            } else if rng.chance(1, 2) {
+                let expected = open.pop().unwrap();
+                let &(_, wrong) = rng.pick(&PAIRS);
+                let wrong = if wrong == expected { ')' } else { wrong };
+                let wrong = if wrong == expected { ']' } else { wrong };
+                line.push(wrong);
+                return line;
+            }
+            incomplete += 1;
+            line
+        })
+        .collect();
+    if incomplete % 2 == 0 {
+        lines.push("<{([".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Octopuses that end up flashing together, an all-zero cavern doing so after ten steps
+fn day11(rng: &mut Rng, size: usize) -> String {
+    for _ in 0..10 {
+        let input = grid(rng, size, size, |rng| digit(rng, 0..=9));
+        let octopuses = day11::input_generator(&input).unwrap();
+        if day11::first_sync(&octopuses, 2000).is_some() {
+            return input;
+        }
+    }
+    grid(rng, size, size, |_| '0')
+}
+
+/// A connected cave system without two big caves next to each other, which would allow
+/// endless paths
+fn day12(rng: &mut Rng, size: usize) -> String {
+    let mut names = HashSet::new();
+    let mut caves = Vec::new();
+    while caves.len() < size {
+        let big = rng.chance(1, 4);
+        let base = if big { b'A' } else { b'a' };
+        let name: String = (0..2)
+            .map(|_| (base + rng.below(26) as u8) as char)
+            .collect();
+        if names.insert(name.clone()) {
+            caves.push((name, big));
+        }
+    }
+    rng.shuffle(&mut caves);
+    caves.insert(0, ("start".to_string(), false));
+    caves.push(("end".to_string(), false));
+
+    let mut edges = HashSet::new();
+    let mut connect = |rng: &mut Rng, i: usize, candidates: &[usize]| {
+        let candidates: Vec<_> = candidates
+            .iter()
+            .copied()
+            .filter(|&j| j != i && !(caves[i].1 && caves[j].1))
+            .collect();
+        if !candidates.is_empty() {
+            let j = *rng.pick(&candidates);
+            edges.insert((i.min(j), i.max(j)));
+        }
+    };
+    // a random tree hanging from `start` keeps everything reachable
+    for i in 1..caves.len() {
+        let earlier: Vec<_> = (0..i).collect();
+        connect(rng, i, &earlier);
+    }
+    let all: Vec<_> = (0..caves.len()).collect();
+    for _ in 0..size / 2 {
+        let i = rng.below(caves.len());
+        connect(rng, i, &all);
+    }
+    let mut edges: Vec<_> = edges.into_iter().collect();
+    edges.sort_unstable();
+    rng.shuffle(&mut edges);
+    join(
+        edges.into_iter().map(|(i, j)| {
+            let (from, to) = if rng.chance(1, 2) { (i, j) } else { (j, i) };
+            format!("{}-{}", caves[from].0, caves[to].0)
+        }),
+        "\n",
+    )
+}
+
+/// Eight random letters unfolded through the same folds as the real puzzle, every lit pixel
+/// getting up to `size` dots on the full sheet
+fn day13(rng: &mut Rng, size: usize) -> String {
+    const FOLDS: [char; 12] = ['x', 'y', 'x', 'y', 'x', 'y', 'x', 'y', 'x', 'y', 'y', 'y'];
+    let mut pixels = Vec::new();
+    for i in 0..8 {
+        let (_, glyph) = rng.pick(&day13::FONT);
+        for (j, _) in glyph.char_indices().filter(|&(_, c)| c == '#') {
+            pixels.push((i * 5 + j % 4, j / 4));
+        }
+    }
+
+    // unfold from the 40x6 code back to the full sheet, the first fold being undone last
+    let (mut width, mut height) = (40, 6);
+    let mut alongs = vec![0; FOLDS.len()];
+    for (along, &axis) in alongs.iter_mut().zip(&FOLDS).rev() {
+        let side = if axis == 'x' { &mut width } else { &mut height };
+        *along = *side;
+        *side = 2 * *side + 1;
+    }
+
+    let mut dots = HashSet::new();
+    for (n, &(x, y)) in pixels.iter().enumerate() {
+        for copy in 0..size {
+            let (mut x, mut y) = (x, y);
+            for (&along, &axis) in alongs.iter().zip(&FOLDS).rev() {
+                // the very first dot is mirrored every time so that it stretches the sheet to
+                // its full size
+                if (n == 0 && copy == 0) || rng.chance(1, 2) {
+                    let coord = if axis == 'x' { &mut x } else { &mut y };
+                    *coord = 2 * along - *coord;
+                }
+            }
+            dots.insert((x, y));
+        }
+    }
+    let mut dots: Vec<_> = dots.into_iter().collect();
+    dots.sort_unstable();
+    rng.shuffle(&mut dots);
+    let dots = join(dots.iter().map(|(x, y)| format!("{},{}", x, y)), "\n");
+    let folds = join(
+        FOLDS
+            .iter()
+            .zip(&alongs)
+            .map(|(axis, along)| format!("fold along {}={}", axis, along)),
+        "\n",
+    );
+    format!("{}\n\n{}", dots, folds)
+}
+
+/// A template of `size` elements with a rule for every pair
+fn day14(rng: &mut Rng, size: usize) -> String {
+    let elements: Vec<char> = "BCFHKNOPSV".chars().collect();
+    let template: String = (0..size.max(2)).map(|_| *rng.pick(&elements)).collect();
+    let mut rules = Vec::new();
+    for &a in &elements {
+        for &b in &elements {
+            rules.push(format!("{}{} -> {}", a, b, rng.pick(&elements)));
+        }
+    }
+    rng.shuffle(&mut rules);
+    format!("{}\n\n{}", template, rules.join("\n"))
+}
+
+fn day15(rng: &mut Rng, size: usize) -> String {
+    grid(rng, size, size, |rng| digit(rng, 1..=9))
+}
+
+fn push_bits(bits: &mut String, value: usize, width: usize) {
+    *bits += &format!("{:0width$b}", value, width = width);
+}
+
+/// Appends a packet of at most `budget` sub-packets, whose products only ever multiply small
+/// literals so that evaluating the transmission cannot overflow
+fn packet(rng: &mut Rng, bits: &mut String, budget: &mut usize, small: bool) {
+    push_bits(bits, rng.below(8), 3);
+    *budget = budget.saturating_sub(1);
+    if small || *budget == 0 || rng.chance(1, 3) {
+        push_bits(bits, 4, 3);
+        let groups = if small { 1 } else { rng.below(5) + 1 };
+        for group in (0..groups).rev() {
+            push_bits(bits, (group > 0) as usize, 1);
+            push_bits(bits, rng.below(16), 4);
+        }
+        return;
+    }
+
+    let kind = *rng.pick(&[0, 1, 2, 3, 5, 6, 7]);
+    let children = match kind {
+        5..=7 => 2,
+        _ => rng.below(3) + 1,
+    };
+    let mut body = String::new();
+    for _ in 0..children {
+        packet(rng, &mut body, budget, kind == 1);
+    }
+    push_bits(bits, kind, 3);
+    if body.len() < 1 << 15 && rng.chance(1, 2) {
+        push_bits(bits, 0, 1);
+        push_bits(bits, body.len(), 15);
+    } else {
+        push_bits(bits, 1, 1);
+        push_bits(bits, children, 11);
+    }
+    *bits += &body;
+}
+
+fn day16(rng: &mut Rng, size: usize) -> String {
+    let mut bits = String::new();
+    packet(rng, &mut bits, &mut size.clone(), false);
+    // the transmission is read a byte at a time
+    while !bits.len().is_multiple_of(8) {
+        bits.push('0');
+    }
+    bits.as_bytes()
+        .chunks(4)
+        .map(|nibble| {
+            let nibble = std::str::from_utf8(nibble).unwrap();
+            format!("{:X}", u8::from_str_radix(nibble, 2).unwrap())
+        })
+        .collect()
+}
+
+/// Some horizontal velocity always stalls above the target, like in the real puzzle
+fn day17(rng: &mut Rng) -> String {
+    let n = rng.range(4..=20);
+    let stall = n * (n + 1) / 2;
+    let x1 = (stall - rng.range(0..=n / 2)).max(1);
+    let x2 = stall + rng.range(0..=30);
+    let y1 = -rng.range(20..=150);
+    let y2 = (y1 + rng.range(5..=15)).min(-1);
+    format!("target area: x={}..{}, y={}..{}", x1, x2, y1, y2)
+}
+
+/// A pair whose regular numbers are already reduced, so at most four pairs deep
+fn snailfish(rng: &mut Rng, depth: usize) -> String {
+    if depth > 1 && (depth == 5 || rng.chance(2, 5)) {
+        return digit(rng, 0..=9).to_string();
+    }
+    format!(
+        "[{},{}]",
+        snailfish(rng, depth + 1),
+        snailfish(rng, depth + 1)
+    )
+}
+
+fn day18(rng: &mut Rng, size: usize) -> String {
+    join((0..size.max(2)).map(|_| snailfish(rng, 1)), "\n")
+}
+
+type Point = [i64; 3];
+
+/// One of the 24 orientations a scanner can face
+fn orientation(rng: &mut Rng) -> ([usize; 3], [i64; 3]) {
+    const PERMUTATIONS: [([usize; 3], i64); 6] = [
+        ([0, 1, 2], 1),
+        ([1, 2, 0], 1),
+        ([2, 0, 1], 1),
+        ([0, 2, 1], -1),
+        ([2, 1, 0], -1),
+        ([1, 0, 2], -1),
+    ];
+    let &(axes, parity) = rng.pick(&PERMUTATIONS);
+    let (x, y) = (*rng.pick(&[-1, 1]), *rng.pick(&[-1, 1]));
+    (axes, [x, y, parity * x * y])
+}
+
+/// A chain of scanners where each one shares at least twelve beacons with the previous one
+fn day19(rng: &mut Rng, size: usize) -> String {
+    const RANGE: i64 = 1000;
+    let mut scanners: Vec<Point> = vec![[0, 0, 0]];
+    for _ in 1..size {
+        let last = scanners[scanners.len() - 1];
+        scanners.push(last.map(|c| c + rng.range(-600..=600)));
+    }
+    let mut beacons = HashSet::new();
+    for (i, scanner) in scanners.iter().enumerate() {
+        let previous = scanners[i.saturating_sub(1)];
+        let mut shared = 0;
+        while shared < 12 {
+            let beacon = [0, 1, 2].map(|a| {
+                let low = scanner[a].max(previous[a]) - RANGE + 1;
+                let high = scanner[a].min(previous[a]) + RANGE - 1;
+                rng.range(low..=high)
+            });
+            if beacons.insert(beacon) {
+                shared += 1;
+            }
+        }
+        for _ in 0..rng.range(0..=10) {
+            beacons.insert(scanner.map(|c| c + rng.range(-RANGE + 1..=RANGE - 1)));
+        }
+    }
+    let mut beacons: Vec<_> = beacons.into_iter().collect();
+    beacons.sort_unstable();
+
+    let mut out = Vec::new();
+    for (i, scanner) in scanners.iter().enumerate() {
+        let (axes, signs) = if i == 0 {
+            ([0, 1, 2], [1, 1, 1])
+        } else {
+            orientation(rng)
+        };
+        let mut seen: Vec<_> = beacons
+            .iter()
+            .map(|b| [0, 1, 2].map(|a| b[a] - scanner[a]))
+            .filter(|b| b.iter().all(|c| c.abs() <= RANGE))
+            .map(|b| [0, 1, 2].map(|a| b[axes[a]] * signs[a]))
+            .collect();
+        rng.shuffle(&mut seen);
+        out.push(format!("--- scanner {} ---", i));
+        out.extend(seen.iter().map(|b| format!("{},{},{}", b[0], b[1], b[2])));
+        out.push(String::new());
+    }
+    out.pop();
+    out.join("\n")
+}
+
+/// An algorithm that makes the infinite background blink, like in the real puzzle
+fn day20(rng: &mut Rng, size: usize) -> String {
+    let pixel = |rng: &mut Rng| if rng.chance(1, 2) { '#' } else { '.' };
+    let mut algorithm: Vec<char> = (0..512).map(|_| pixel(rng)).collect();
+    algorithm[0] = '#';
+    algorithm[511] = '.';
+    let image = grid(rng, size, size, pixel);
+    format!("{}\n\n{}", algorithm.into_iter().collect::<String>(), image)
+}
+
+fn day21(rng: &mut Rng) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+        rng.range(1..=10),
+        rng.range(1..=10)
+    )
+}
+
+/// The first twenty steps stay in the initialization region, the others go far beyond it
+fn day22(rng: &mut Rng, size: usize) -> String {
+    join(
+        (0..size).map(|i| {
+            let state = if i == 0 || rng.chance(3, 5) {
+                "on"
+            } else {
+                "off"
+            };
+            let (reach, extent) = if i < 20 { (50, 40) } else { (90_000, 30_000) };
+            let ranges: Vec<_> = ["x", "y", "z"]
+                .iter()
+                .map(|axis| {
+                    let start = rng.range(-reach..=reach - extent);
+                    let end = start + rng.range(0..=extent);
+                    format!("{}={}..{}", axis, start, end)
+                })
+                .collect();
+            format!("{} {}", state, ranges.join(","))
+        }),
+        "\n",
+    )
+}
+
+/// One of the burrows that can still be organized once the hidden rows are unfolded, which
+/// rules out about an eighth of them
+fn day23(rng: &mut Rng) -> String {
+    let mut amphipods: Vec<char> = "AABBCCDD".chars().collect();
+    loop {
+        rng.shuffle(&mut amphipods);
+        let a = &amphipods;
+        let input = format!(
+            "#############\n#...........#\n###{}#{}#{}#{}###\n  #{}#{}#{}#{}#\n  #########",
+            a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]
+        );
+        let rooms = day23::input_generator(&input).unwrap();
+        if day23::organize(&day23::unfold(&rooms)).is_some() {
+            return input;
+        }
+    }
+}
+
+/// Fourteen blocks of the MONAD program: half of them push `digit + offset` on a base 26 stack and
+/// the other half only pop it when their digit differs from the pushed one by a small amount
+fn day24(rng: &mut Rng) -> String {
+    let mut pushes = 7;
+    let mut stack = Vec::new();
+    let mut blocks = Vec::new();
+    for i in 0..14 {
+        let remaining = 14 - i;
+        let push = stack.is_empty() || (pushes > 0 && stack.len() < remaining && rng.chance(1, 2));
+        let (div, check, offset) = if push {
+            pushes -= 1;
+            let offset = rng.range(1..=16);
+            stack.push(offset);
+            (1, rng.range(10..=16), offset)
+        } else {
+            let pushed = stack.pop().unwrap();
+            (26, rng.range(-8..=8) - pushed, rng.range(1..=16))
+        };
+        blocks.push(format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\n\
+             mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\n\
+             mul y x\nadd z y",
+            div, check, offset
+        ));
+    }
+    blocks.join("\n")
+}
+
+/// A herd that eventually stops, falling back to one packed so tight that it never moves
+fn day25(rng: &mut Rng, size: usize) -> String {
+    for _ in 0..10 {
+        let input = grid(rng, size, size, |rng| match rng.below(4) {
+            0 => '>',
+            1 => 'v',
+            _ => '.',
+        });
+        let herd = day25::input_generator(&input).unwrap();
+        if day25::settle(&herd, 10_000).is_some() {
+            return input;
+        }
+    }
+    grid(rng, size, size, |_| '>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(generate(4, 3, 7), generate(4, 3, 7));
+        assert_ne!(generate(4, 3, 7), generate(4, 3, 8));
+        assert_eq!(generate(26, 3, 7), None);
+        let mut rng = Rng::new(1);
+        let mut items: Vec<_> = (0..10).collect();
+        rng.shuffle(&mut items);
+        items.sort_unstable();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+        assert!((0..100).all(|_| (-3..=3).contains(&rng.range(-3..=3))));
+    }
+
+    #[test]
+    fn test_solvable() {
+        for day in registry::days() {
+            for seed in 0..2 {
+                let input = generate(day.day, 5, seed).unwrap();
+                let parsed = day
+                    .generate(&input)
+                    .unwrap_or_else(|e| panic!("seed {}: {}\n{}", seed, e, input));
+                for part in day.parts() {
+                    let answers: Vec<_> = day
+                        .variants(part)
+                        .map(|s| {
+                            s.solve(&parsed)
+                                .unwrap_or_else(|e| panic!("seed {}: {}", seed, e))
+                        })
+                        .collect();
+                    assert!(
+                        answers.windows(2).all(|w| w[0] == w[1]),
+                        "day {} part {} seed {}: variants disagree {:?}",
+                        day.day,
+                        part,
+                        seed,
+                        answers
+                    );
+                }
+            }
+        }
+    }
+}
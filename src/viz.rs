@@ -0,0 +1,99 @@
+//! Terminal animations of the days that evolve step by step. Every day renders its own frames
+//! from a fresh copy of the parsed input, so watching a day never changes its answers.
+
+use crate::registry::{downcast, Input};
+use crate::{day11, day13, day15, day20};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+pub type Frames = Box<dyn Iterator<Item = String>>;
+
+/// Largest picture drawn one character per cell, bigger ones get shrunk to fit
+const MAX_WIDTH: usize = 160;
+const MAX_HEIGHT: usize = 60;
+
+/// Frames of `day` on its parsed `input`, or `None` for a day without an animation
+pub fn animate(day: u8, input: &Input) -> Option<Frames> {
+    let frames = match day {
+        11 => day11::frames(Clone::clone(downcast(input))),
+        13 => day13::frames(Clone::clone(downcast(input))),
+        15 => day15::frames(Clone::clone(downcast(input))),
+        20 => day20::frames(Clone::clone(downcast(input))),
+        _ => return None,
+    };
+    Some(frames)
+}
+
+/// Draws every frame over the previous one, waiting `delay` after each of them
+pub fn play<W: Write>(frames: Frames, out: &mut W, delay: Duration) -> io::Result<()> {
+    for frame in frames {
+        // move to the top left corner and clear the screen
+        writeln!(out, "\x1b[H\x1b[2J{}", frame)?;
+        out.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+/// Draws a black and white picture, where a character stands for a square block of cells when
+/// the picture is too big for a terminal
+pub fn picture<F: Fn((usize, usize)) -> bool>(width: usize, height: usize, lit: F) -> String {
+    let scale = width
+        .div_ceil(MAX_WIDTH)
+        .max(height.div_ceil(MAX_HEIGHT))
+        .max(1);
+    let mut out = String::new();
+    for row in (0..height).step_by(scale) {
+        if row > 0 {
+            out.push('\n');
+        }
+        for col in (0..width).step_by(scale) {
+            let block = (row..height.min(row + scale))
+                .flat_map(|r| (col..width.min(col + scale)).map(move |c| (r, c)));
+            out.push(if block.into_iter().any(&lit) {
+                '#'
+            } else {
+                '.'
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    fn parse(day: u8, input: &str) -> Input {
+        registry::day(day).unwrap().generate(input).unwrap()
+    }
+
+    #[test]
+    fn test_picture() {
+        let diagonal = |(row, col): (usize, usize)| row == col;
+        assert_eq!(picture(3, 2, diagonal), "#..\n.#.");
+        let big = picture(2 * MAX_WIDTH, 10, diagonal);
+        assert_eq!(big.lines().count(), 5);
+        assert!(big.starts_with("#.."));
+        assert_eq!(picture(0, 0, diagonal), "");
+    }
+
+    #[test]
+    fn test_animate() {
+        let octopuses = "5483143223\n2745854711\n5264556173\n6141336146\n6357385478\n\
+                         4167524645\n2176841721\n6882881134\n4846848554\n5283751526";
+        let frames: Vec<_> = animate(11, &parse(11, octopuses)).unwrap().collect();
+        // the sample syncs on step 195, after the frame of the initial state
+        assert_eq!(frames.len(), 196);
+        assert!(frames[195].starts_with("step 195, "));
+
+        let paper = "0,0\n4,0\n0,4\n4,4\n\nfold along x=2";
+        let frames: Vec<_> = animate(13, &parse(13, paper)).unwrap().collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].lines().nth(1), Some("#."));
+
+        assert!(animate(1, &parse(1, "199\n200")).is_none());
+    }
+}